@@ -1,19 +1,42 @@
 use pushrod::base_widget::BaseWidget;
 use pushrod::box_widget::BoxWidget;
 use pushrod::engine::Engine;
+use pushrod::event::{EventHandler, HandlerContext, PushrodEvent};
 use pushrod::geometry::{point, size};
-use pushrod::widget::{SystemWidget, Widget};
+use pushrod::widget::{SystemWidget, Widget, WidgetId};
 use sdl2::pixels::Color;
 use sdl2::ttf::FontStyle;
 use pushrod::button_widget::ButtonWidget;
 use pushrod::text_widget::{TextJustify, TextWidget};
 
+/// Updates the welcome text each time the button is clicked.
+struct ClickHandler {
+    button_id: WidgetId,
+    text_id: WidgetId,
+    clicks: u32,
+}
+
+impl EventHandler for ClickHandler {
+    fn process_event(&mut self, event: PushrodEvent, context: &mut HandlerContext) {
+        if let PushrodEvent::Clicked(widget_id, _) = event {
+            if widget_id == self.button_id {
+                self.clicks += 1;
+
+                if let Some(text_widget) = context.get_widget_as::<TextWidget>(self.text_id) {
+                    text_widget.set_text(format!("Clicked {} time(s)", self.clicks));
+                }
+            }
+        }
+    }
+}
+
 pub fn main() {
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
     let window = video_subsystem
         .window("pushrod", 800, 600)
         .position_centered()
+        .resizable()
         .opengl()
         .build()
         .unwrap();
@@ -52,14 +75,19 @@ pub fn main() {
 
     eprintln!("Added box widget ID: {}", box_widget_id3);
 
-    let mut button1_widget = ButtonWidget::new(point(40, 180), size(120, 20),
+    let button1_widget = ButtonWidget::new(point(40, 180), size(120, 20),
         String::from("assets/OpenSans-Regular.ttf"), FontStyle::NORMAL, 14, Color::BLACK,
         TextJustify::Center, String::from("Click me"), 2);
-    button1_widget.set_id(1);
     let button_widget_id1 = engine.add_widget(SystemWidget::Button(Box::new(button1_widget)));
 
     eprintln!("Added button widget ID: {}", button_widget_id1);
 
+    engine.add_event_handler(Box::new(ClickHandler {
+        button_id: button_widget_id1,
+        text_id: text_widget_id1,
+        clicks: 0,
+    }));
+
     // let mut new_base_widget = BaseWidget::new(make_points(100, 100), make_size(600, 400));
     //
     // new_base_widget
@@ -153,5 +181,5 @@ pub fn main() {
     //     false
     // });
 
-    engine.run(sdl_context, window);
+    engine.run(sdl_context, window).unwrap();
 }
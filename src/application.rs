@@ -0,0 +1,259 @@
+// Application
+// Pushrod
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Application
+//!
+//! Runs several windows from a single run loop.  Each window is driven by its own `Engine`, so it
+//! has its own `WidgetCache`, event handler, timers and canvas.  This is used for tool palettes,
+//! detached dialogs, and any other UI that spans more than one window.
+//!
+//! `SDL2` delivers the events for every window through a single event pump, so the `Application`
+//! routes each event to the `Engine` of the window it belongs to, using its `window_id`.  Events
+//! that do not belong to any window, such as a quit request, are sent to every `Engine`.
+//!
+//! Closing a window shuts down its `Engine`, and the window is removed from the `Application`.
+//! Likewise, a window is closed when its `Engine` is shut down.  The run loop ends once every
+//! window has been closed, or a quit event is received.
+//!
+//! ```rust,no_run
+//! # use pushrod::application::Application;
+//! # use pushrod::engine::Engine;
+//! # use pushrod::geometry::size;
+//! let sdl_context = sdl2::init().unwrap();
+//! let video_subsystem = sdl_context.video().unwrap();
+//! let main_window = video_subsystem.window("main", 800, 600).build().unwrap();
+//! let palette_window = video_subsystem.window("palette", 200, 400).build().unwrap();
+//! let mut application = Application::new(sdl_context);
+//!
+//! application.add_window(Engine::new(size(800, 600), 60), main_window).unwrap();
+//! application.add_window(Engine::new(size(200, 400), 60), palette_window).unwrap();
+//! application.run().unwrap();
+//! ```
+
+use crate::engine::{wait_for_event, Engine};
+use crate::error::PushrodError;
+use log::debug;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::pixels::Color;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+use sdl2::Sdl;
+use std::borrow::BorrowMut;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// A single window managed by the run loop, along with the `Engine` that drives it.  The `Engine`
+/// is owned by an `Application`, and borrowed by `Engine::run`.
+pub(crate) struct ApplicationWindow<E: BorrowMut<Engine> = Engine> {
+    window_id: u32,
+    engine: E,
+    canvas: Canvas<Window>,
+}
+
+impl<E: BorrowMut<Engine>> ApplicationWindow<E> {
+    /// Creates the canvas for the `Window`, and attaches the waker of the `Engine` to the `SDL2`
+    /// context, so that posted messages wake the run loop.  Returns an error if the canvas could
+    /// not be created.
+    pub(crate) fn new(sdl: &Sdl, engine: E, window: Window) -> Result<Self, PushrodError> {
+        let window_id = window.id();
+
+        // Initializes the canvas, creating a textured canvas against which GPU textures will be
+        // used, specifying hardware acceleration.
+        let mut canvas = window
+            .into_canvas()
+            .target_texture()
+            .accelerated()
+            .build()?;
+
+        // Sets the screen color to all white, clears and presents the canvas to display.
+        canvas.set_draw_color(Color::RGBA(255, 255, 255, 255));
+        canvas.clear();
+        canvas.present();
+
+        let mut engine = engine;
+
+        engine.borrow_mut().set_waker(sdl);
+
+        Ok(Self {
+            window_id,
+            engine,
+            canvas,
+        })
+    }
+}
+
+/// Manages a list of windows, each driven by its own `Engine`, from a single run loop.
+pub struct Application {
+    sdl: Sdl,
+    windows: Vec<ApplicationWindow>,
+}
+
+impl Application {
+    /// Creates a new `Application` against an initialized `SDL2` context.
+    pub fn new(sdl: Sdl) -> Self {
+        Self {
+            sdl,
+            windows: Vec::new(),
+        }
+    }
+
+    /// Adds a `Window` to the `Application`, to be driven by the `Engine` specified.  Returns
+    /// the `window_id` of the `Window`, which is used to retrieve its `Engine`, or an error if
+    /// the canvas for the `Window` could not be created.
+    pub fn add_window(&mut self, engine: Engine, window: Window) -> Result<u32, PushrodError> {
+        let window = ApplicationWindow::new(&self.sdl, engine, window)?;
+        let window_id = window.window_id;
+
+        self.windows.push(window);
+
+        Ok(window_id)
+    }
+
+    /// Retrieves the `Engine` driving the window with the `window_id` specified, `None` if the
+    /// window was not found, or has been closed.
+    pub fn get_engine(&mut self, window_id: u32) -> Option<&mut Engine> {
+        self.windows
+            .iter_mut()
+            .find(|x| x.window_id == window_id)
+            .map(|x| &mut x.engine)
+    }
+
+    /// Returns the `window_id`s of the windows that are still open.
+    pub fn get_window_ids(&self) -> Vec<u32> {
+        self.windows.iter().map(|x| x.window_id).collect()
+    }
+
+    /// The main run loop.  For each window in turn, events are handled, followed by replayed
+    /// events, posted messages and timers, and then any invalidated `Widget`s are drawn.  Runs
+    /// until every window has been closed.  Returns an error if the event pump could not be
+    /// created, or a window could not be drawn.
+    pub fn run(&mut self) -> Result<(), PushrodError> {
+        run_windows(&self.sdl, &mut self.windows)
+    }
+}
+
+/// The run loop shared by `Application::run` and `Engine::run`.  It performs the following tasks
+/// in the following order:
+///
+/// First, it pulls all of the events that have been generated by the OS from the event pump, and
+/// routes each one to the `Engine` of the window it belongs to.  Events that do not belong to a
+/// window, such as a quit request, are sent to every `Engine`.
+///
+/// Second, each `Engine` processes any recorded events being replayed, events and closures posted
+/// from other threads, and timers that have become due.  Windows whose `Engine` has been shut
+/// down are then closed, and the loop ends once no windows remain.
+///
+/// Third, if any `Widget`s are invalidated, every window is drawn and presented.  Frames are never
+/// drawn more often than the highest frame rate of any window.
+///
+/// Afterward, the loop blocks until the next input event, timer, posted message or replayed event
+/// arrives, so no CPU time is used while the application is idle.  If any window polls for events
+/// rather than waiting, the loop wakes at least once per frame.
+pub(crate) fn run_windows<E: BorrowMut<Engine>>(
+    sdl: &Sdl,
+    windows: &mut Vec<ApplicationWindow<E>>,
+) -> Result<(), PushrodError> {
+    let mut event_pump = sdl.event_pump().map_err(PushrodError::Sdl)?;
+    let run_start = Instant::now();
+    let mut last_frame: Option<Instant> = None;
+    let mut pending_event: Option<Event> = None;
+
+    loop {
+        let start = Instant::now();
+
+        // Route each event to the window it belongs to, starting with the event that woke the
+        // loop.
+        for event in pending_event.take().into_iter().chain(event_pump.poll_iter()) {
+            let window_id = event.get_window_id();
+
+            for window in windows.iter_mut() {
+                let engine = window.engine.borrow_mut();
+
+                if !engine.is_running() || window_id.is_some_and(|x| x != window.window_id) {
+                    continue;
+                }
+
+                if let Event::Window {
+                    win_event: WindowEvent::Close,
+                    ..
+                } = event
+                {
+                    debug!(target: "pushrod::event", "Window {} closed", window.window_id);
+
+                    engine.shutdown();
+                } else if !engine.handle_event(event.clone()) {
+                    engine.shutdown();
+                }
+            }
+        }
+
+        for window in windows.iter_mut() {
+            let engine = window.engine.borrow_mut();
+
+            if engine.is_running() && !engine.process_pending(run_start) {
+                engine.shutdown();
+            }
+        }
+
+        // Closed windows are dropped, which destroys them.
+        windows.retain_mut(|x| x.engine.borrow_mut().is_running());
+
+        if windows.is_empty() {
+            return Ok(());
+        }
+
+        // Frames are paced using the highest frame rate of any window.
+        let frame_duration = windows
+            .iter_mut()
+            .map(|x| x.engine.borrow_mut().get_frame_duration())
+            .min()
+            .unwrap_or_default();
+
+        if windows
+            .iter_mut()
+            .any(|x| x.engine.borrow_mut().get_widget_cache().needs_redraw())
+        {
+            if let Some(last_frame) = last_frame {
+                sleep(frame_duration.saturating_sub(last_frame.elapsed()));
+            }
+
+            for window in windows.iter_mut() {
+                if window.engine.borrow_mut().draw(&mut window.canvas)? {
+                    window.canvas.present();
+                }
+            }
+
+            last_frame = Some(Instant::now());
+        }
+
+        // The loop waits for the earliest timer or replayed event of any window.  If any window
+        // polls for events, the loop wakes at least once per frame.
+        let mut timeout: Option<Duration> = windows
+            .iter_mut()
+            .filter_map(|x| x.engine.borrow_mut().get_wait_timeout(run_start))
+            .min();
+
+        if windows
+            .iter_mut()
+            .any(|x| !x.engine.borrow_mut().is_waiting_for_events())
+        {
+            let delay = frame_duration.saturating_sub(start.elapsed());
+
+            timeout = Some(timeout.map_or(delay, |x| x.min(delay)));
+        }
+
+        pending_event = wait_for_event(&mut event_pump, timeout);
+    }
+}
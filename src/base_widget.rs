@@ -18,10 +18,11 @@
 //! This is a default widget that can be used as a top-level canvas.  It is a basic `Widget`
 //! implementation that draws a background, and fills it in with the desired color.
 
-use crate::event::PushrodEvent;
+use crate::error::PushrodError;
+use crate::event::{EventContext, PushrodEvent};
 use crate::geometry::{Point, Size};
 use crate::texture::TextureStore;
-use crate::widget::Widget;
+use crate::widget::{Widget, WidgetId};
 use sdl2::pixels::Color;
 use sdl2::render::{Canvas, Texture};
 use sdl2::video::Window;
@@ -29,13 +30,15 @@ use std::any::Any;
 use crate::font::FontCache;
 
 /// BaseWidget structure containing the point of origin, size, base color, an invalidation flag,
-/// and texture store for drawing.
+/// visibility and enabled flags, and texture store for drawing.
 pub struct BaseWidget {
-    id: i32,
+    id: WidgetId,
     origin: Point,
     size: Size,
     base_color: Color,
     invalidated: bool,
+    visible: bool,
+    enabled: bool,
     texture: TextureStore,
 }
 
@@ -45,11 +48,16 @@ impl Widget for BaseWidget {
         self
     }
 
+    /// Returns self object as a mutable `Any` object.
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     /// Returns the ID for the `Widget`.
-    fn get_id(&self) -> i32 { self.id }
+    fn get_id(&self) -> WidgetId { self.id }
 
     /// Sets the ID for the `Widget`.
-    fn set_id(&mut self, id: i32) { self.id = id; }
+    fn set_id(&mut self, id: WidgetId) { self.id = id; }
 
     /// Returns the `Point` of origin for the `Widget`.
     fn get_origin(&self) -> &Point {
@@ -95,6 +103,28 @@ impl Widget for BaseWidget {
         self.invalidated
     }
 
+    /// Sets the visibility flag, invalidating the object in the process for redrawing.
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        self.set_invalidated(true);
+    }
+
+    /// Returns a flag indicating whether or not the `Widget` is visible.
+    fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Sets the enabled flag, invalidating the object in the process for redrawing.
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.set_invalidated(true);
+    }
+
+    /// Returns a flag indicating whether or not the `Widget` is enabled.
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
     /// Returns the `TextureStore` of the `Widget`.
     fn get_texture(&mut self) -> &mut TextureStore {
         &mut self.texture
@@ -102,23 +132,22 @@ impl Widget for BaseWidget {
 
     /// Handles any `PushrodEvent` objects.  Since this is a `BaseWidget`, it does not handle or
     /// generate any events.
-    fn handle_event(&self, _event: PushrodEvent) -> Option<&[PushrodEvent]> { None }
+    fn handle_event(&mut self, _event: PushrodEvent, _context: &mut EventContext) {}
 
     /// Draws the object.
-    fn draw(&mut self, c: &mut Canvas<Window>, _fc: &mut FontCache) -> Option<&Texture> {
+    fn draw(&mut self, c: &mut Canvas<Window>, _fc: &mut FontCache) -> Result<Option<&Texture>, PushrodError> {
         if self.invalidated {
-            self.texture.create_or_resize_texture(c, self.size);
+            self.texture.create_or_resize_texture(c, self.size)?;
 
             let base_color = self.base_color;
 
             c.with_texture_canvas(self.texture.get_mut_ref(), |texture| {
                 texture.set_draw_color(base_color);
                 texture.clear();
-            })
-            .unwrap();
+            })?;
         }
 
-        self.texture.get_optional_ref()
+        Ok(self.texture.get_optional_ref())
     }
 }
 
@@ -126,11 +155,13 @@ impl BaseWidget {
     /// Creates a new `BaseWidget` given the `Point` of origin and its `Size`.
     pub fn new(origin: Point, size: Size) -> Self {
         Self {
-            id: 0,
+            id: WidgetId::default(),
             origin,
             size,
             base_color: Color::RGBA(255, 255, 255, 0),
             invalidated: true,
+            visible: true,
+            enabled: true,
             texture: TextureStore::default(),
         }
     }
@@ -19,10 +19,11 @@
 //! create a border of a specific width and color.
 
 use crate::base_widget::BaseWidget;
-use crate::event::PushrodEvent;
+use crate::error::PushrodError;
+use crate::event::{EventContext, PushrodEvent};
 use crate::geometry::{origin_point, make_rect, Point, Size};
 use crate::texture::TextureStore;
-use crate::widget::Widget;
+use crate::widget::{Widget, WidgetId};
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::{Canvas, Texture};
@@ -32,10 +33,12 @@ use crate::font::FontCache;
 use crate::impl_widget_base;
 
 pub struct BoxWidget {
-    id: i32,
+    id: WidgetId,
     origin: Point,
     size: Size,
     invalidated: bool,
+    visible: bool,
+    enabled: bool,
     texture: TextureStore,
     base_widget: BaseWidget,
     border_color: Color,
@@ -43,43 +46,57 @@ pub struct BoxWidget {
 }
 
 impl Widget for BoxWidget {
-    fn handle_event(&self, _event: PushrodEvent) -> Option<&[PushrodEvent]> { None }
+    fn handle_event(&mut self, _event: PushrodEvent, _context: &mut EventContext) {}
 
-    fn draw(&mut self, c: &mut Canvas<Window>, fc: &mut FontCache) -> Option<&Texture> {
+    fn draw(&mut self, c: &mut Canvas<Window>, fc: &mut FontCache) -> Result<Option<&Texture>, PushrodError> {
         if self.invalidated && self.border_width > 0 {
-            self.texture.create_or_resize_texture(c, self.size);
+            self.texture.create_or_resize_texture(c, self.size)?;
 
-            let base_widget_texture = self.base_widget.draw(c, fc).unwrap();
+            let base_widget_texture = self.base_widget.draw(c, fc)?;
             let border_color = self.border_color;
             let border_width = self.border_width;
             let widget_size = self.size;
             let widget_width = self.size.w;
             let widget_height = self.size.h;
 
+            let mut result = Ok(());
+
             c.with_texture_canvas(self.texture.get_mut_ref(), |texture| {
-                texture
-                    .copy(
+                if let Some(base_widget_texture) = base_widget_texture {
+                    result = texture.copy(
                         base_widget_texture,
                         None,
                         make_rect(origin_point(), widget_size),
-                    )
-                    .unwrap();
+                    );
+                }
 
                 texture.set_draw_color(border_color);
 
                 for i in 0..border_width as i32 {
-                    let computed_width = (widget_width as u32 - (i as u32 * 2u32)) as u32;
-                    let computed_height = (widget_height as u32 - (i as u32 * 2u32)) as u32;
+                    let computed_width = (widget_width as u32).saturating_sub(i as u32 * 2u32);
+                    let computed_height = (widget_height as u32).saturating_sub(i as u32 * 2u32);
+
+                    // Borders wider than half of the box fill it, and leave no room for more.
+                    if computed_width == 0 || computed_height == 0 {
+                        break;
+                    }
 
-                    texture
-                        .draw_rect(Rect::new(i, i, computed_width, computed_height))
-                        .unwrap();
+                    if result.is_ok() {
+                        result = texture.draw_rect(Rect::new(i, i, computed_width, computed_height));
+                    }
                 }
-            })
-            .unwrap();
+            })?;
+
+            result.map_err(PushrodError::Render)?;
         }
 
-        self.texture.get_optional_ref()
+        Ok(self.texture.get_optional_ref())
+    }
+
+    /// Destroys the `Texture`s of the `BoxWidget`, its base.
+    fn destroy_textures(&mut self) {
+        self.texture.destroy();
+        self.base_widget.destroy_textures();
     }
 
     impl_widget_base!();
@@ -90,10 +107,12 @@ impl BoxWidget {
     /// Any borders with a width of 0 will not be drawn.
     pub fn new(origin: Point, size: Size, border_color: Color, border_width: u8) -> Self {
         Self {
-            id: 0,
+            id: WidgetId::default(),
             origin: origin.clone(),
             size: size.clone(),
             invalidated: true,
+            visible: true,
+            enabled: true,
             texture: TextureStore::default(),
             base_widget: BaseWidget::new(origin_point(), size),
             border_color,
@@ -17,8 +17,13 @@
 //!
 //! This is a simple system `Widget` that utilizes the `BaseWidget`, and draws on its canvas
 //! using a `TextWidget` to display a text message within its bounds.  Displays a border
-//! and a filled button with a 3D-like effect.  Generates a `Clicked(u32, u8)` event when
-//! a mouse button is clicked and released within its bounds.
+//! and a filled button with a 3D-like effect.  Generates a `Clicked(WidgetId, u8)` event when
+//! the left mouse button is clicked and released within its bounds.
+//!
+//! While the mouse button is held, the button is drawn in its pressed state.  If the pointer
+//! leaves the bounds of the button before the mouse button is released, the click is cancelled.
+//! The second argument of `Clicked` is the click count reported by `SDL2`, which is `2` for a
+//! double-click, `3` for a triple-click, and so on.
 
 use sdl2::pixels::Color;
 use sdl2::render::{Canvas, Texture};
@@ -26,126 +31,192 @@ use sdl2::ttf::FontStyle;
 use sdl2::video::Window;
 use std::any::Any;
 use sdl2::event::Event::{MouseButtonDown, MouseButtonUp};
+use sdl2::mouse::MouseButton;
 use sdl2::rect::Rect;
 use crate::base_widget::BaseWidget;
-use crate::event::PushrodEvent;
+use crate::error::PushrodError;
+use crate::event::{EventContext, PushrodEvent};
 use crate::font::FontCache;
 use crate::geometry::{make_rect, origin_point, Point, point, Size};
 use crate::text_widget::{TextJustify, TextWidget};
 use crate::texture::TextureStore;
-use crate::widget::Widget;
+use crate::widget::{Widget, WidgetId};
 use crate::impl_widget_base;
+use log::debug;
 
 pub struct ButtonWidget {
-    id: i32,
+    id: WidgetId,
     origin: Point,
     size: Size,
     invalidated: bool,
+    visible: bool,
+    enabled: bool,
     texture: TextureStore,
     base_widget: BaseWidget,
     text_widget: TextWidget,
     border_width: u8,
+    font_color: Color,
     selected_state: bool,
 }
 
 impl Widget for ButtonWidget {
-    fn handle_event(&self, event: PushrodEvent) -> Option<&[PushrodEvent]> {
+    fn handle_event(&mut self, event: PushrodEvent, context: &mut EventContext) {
         match event {
-            PushrodEvent::SystemEvent(widget_id, x) => {
-                match &x {
-                    MouseButtonDown {
-                        mouse_btn, clicks, ..
-                    } => {
-                        eprintln!("[ButtonWidget] Button down: {:?} clicks={:?}", mouse_btn, clicks);
-                    }
+            PushrodEvent::SystemEvent(_, MouseButtonDown { mouse_btn: MouseButton::Left, .. }) => {
+                self.set_pressed(true);
+            }
 
-                    MouseButtonUp {
-                        mouse_btn, clicks, ..
-                    } => {
-                        eprintln!("[ButtonWidget] Button up: {:?} clicks={:?}", mouse_btn, clicks);
-                    }
+            // The click count is passed through, so that double and triple clicks can be
+            // distinguished by the application.
+            PushrodEvent::SystemEvent(_, MouseButtonUp { mouse_btn: MouseButton::Left, clicks, .. })
+                if self.selected_state =>
+            {
+                self.set_pressed(false);
 
-                    _default => {
-                        eprintln!("[ButtonWidget] Wrapped SystemEvent: ID={:?} Widget={:?} {:?}", self.id, widget_id, &x);
-                    }
-                }
+                debug!(target: "pushrod::event", "Button {} clicked: clicks={}", self.id, clicks);
+
+                context.push_event(PushrodEvent::Clicked(self.id, clicks));
+            }
 
+            // Leaving the bounds of the button while it is held cancels the click, as the release
+            // will not be sent to this button.
+            PushrodEvent::BoundsChange(from_widget_id, _) if from_widget_id == self.id => {
+                self.set_pressed(false);
             }
 
             _default => {}
         }
-
-        None
     }
 
-    fn draw(&mut self, c: &mut Canvas<Window>, fc: &mut FontCache) -> Option<&Texture> {
+    fn draw(&mut self, c: &mut Canvas<Window>, fc: &mut FontCache) -> Result<Option<&Texture>, PushrodError> {
         if self.invalidated && self.border_width > 0 {
-            self.texture.create_or_resize_texture(c, self.size);
+            self.texture.create_or_resize_texture(c, self.size)?;
 
+            // Pressed buttons are drawn inverted: white text on a black background.
             if self.selected_state {
-                self.base_widget.set_color(Color::BLACK);
-                self.text_widget.set_color(Color::WHITE);
+                self.text_widget.set_font_color(Color::WHITE);
+            } else {
+                self.text_widget.set_font_color(self.font_color);
             }
 
-            let base_widget_texture = self.base_widget.draw(c, fc).unwrap();
-            let text_widget_texture = self.text_widget.draw(c, fc).unwrap();
-            // let border_color = self.border_color;
+            self.text_widget.set_invalidated(true);
+
+            // Buttons too small to fit text inside their borders are drawn without it.
             let border_width = self.border_width as u32;
+            let text_size = Size::new(
+                self.size.w.saturating_sub((border_width + 1) * 2),
+                self.size.h.saturating_sub((border_width + 1) * 2),
+            );
+            let text_widget_size = *self.text_widget.get_size();
+            let has_text = text_size.w > 0 && text_size.h > 0 && text_widget_size.w > 0 && text_widget_size.h > 0;
+            let base_widget_texture = self.base_widget.draw(c, fc)?;
+            let text_widget_texture = if has_text {
+                self.text_widget.draw(c, fc)?
+            } else {
+                None
+            };
+            // let border_color = self.border_color;
             let widget_size = self.size;
+            let selected_state = self.selected_state;
             // let widget_width = self.size.w;
             // let widget_height = self.size.h;
 
-            #[allow(unused_must_use)]
+            let mut result = Ok(());
+
             c.with_texture_canvas(self.texture.get_mut_ref(), |texture| {
                 // Draw the background
-                &texture
-                    .copy(
+                if let Some(base_widget_texture) = base_widget_texture {
+                    result = texture.copy(
                         base_widget_texture,
                         None,
                         make_rect(origin_point(), widget_size),
-                    )
-                    .unwrap();
+                    );
+                }
+
+                if selected_state {
+                    texture.set_draw_color(Color::BLACK);
+                    if result.is_ok() {
+                        result = texture.fill_rect(None);
+                    }
+                }
 
                 // Overlay the text
-                &texture
-                    .copy(
-                        text_widget_texture,
-                        None,
-                        make_rect(point((border_width + 1) as i32, (border_width + 1) as i32),
-                                  Size::new(self.size.w - ((border_width + 1) * 2), self.size.h - ((border_width + 1) * 2))),
-                    )
-                    .unwrap();
+                if let Some(text_widget_texture) = text_widget_texture {
+                    if result.is_ok() {
+                        result = texture.copy(
+                            text_widget_texture,
+                            None,
+                            make_rect(point((border_width + 1) as i32, (border_width + 1) as i32), text_size),
+                        );
+                    }
+                }
 
                 // Draw the border
                 texture.set_draw_color(Color::BLACK);
-                texture.draw_rect(Rect::new(0, 0, widget_size.w, widget_size.h))
-                    .unwrap();
-            })
-                .unwrap();
+                if result.is_ok() {
+                    result = texture.draw_rect(Rect::new(0, 0, widget_size.w, widget_size.h));
+                }
+            })?;
+
+            result.map_err(PushrodError::Render)?;
         }
 
-        self.texture.get_optional_ref()
+        Ok(self.texture.get_optional_ref())
+    }
+
+    /// Buttons can be focused, so that they can be reached using the keyboard.
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    /// Destroys the `Texture`s of the `ButtonWidget`, its base, and its `TextWidget`.
+    fn destroy_textures(&mut self) {
+        self.texture.destroy();
+        self.base_widget.destroy_textures();
+        self.text_widget.destroy_textures();
     }
 
-    impl_widget_base!();
+    /// Disabling a button cancels any press in progress, as the release of the mouse button is not
+    /// sent to a disabled button.  Otherwise, the next release after the button is enabled again
+    /// would generate a `Clicked` event without a matching press.
+    fn set_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.cancel_interaction();
+        }
+
+        self.enabled = enabled;
+        self.set_invalidated(true);
+    }
+
+    /// Cancels any press in progress, such as when a parent of the button is disabled.
+    fn cancel_interaction(&mut self) {
+        self.set_pressed(false);
+    }
+
+    impl_widget_base!(custom_set_enabled);
 }
 
 impl ButtonWidget {
     /// Creates a new `BoxWidget` based on its point of origin, size, border color, and border width.
     /// Any borders with a width of 0 will not be drawn.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(origin: Point, size: Size, font_name: String, font_style: FontStyle, font_size: u16,
                font_color: Color, justification: TextJustify, msg: String, border_width: u8) -> Self {
         Self {
-            id: 0,
-            origin: origin.clone(),
-            size: size.clone(),
+            id: WidgetId::default(),
+            origin,
+            size,
             invalidated: true,
+            visible: true,
+            enabled: true,
             texture: TextureStore::default(),
             base_widget: BaseWidget::new(origin_point(), size),
             text_widget: TextWidget::new(origin_point(),
-                                         Size::new(size.w - (border_width as u32 * 2), size.h - (border_width as u32 * 2)),
+                                         Size::new(size.w.saturating_sub(border_width as u32 * 2),
+                                                   size.h.saturating_sub(border_width as u32 * 2)),
                                          font_name, font_style, font_size, font_color, justification, msg),
             border_width,
+            font_color,
             selected_state: false,
         }
     }
@@ -161,8 +232,175 @@ impl ButtonWidget {
         self.border_width
     }
 
-    /// Changes the text displayed inside the button.
+    /// Indicates whether or not the button is currently pressed: a mouse button was pressed
+    /// inside its bounds, and has not yet been released.
+    pub fn is_pressed(&self) -> bool {
+        self.selected_state
+    }
+
+    /// Internal function that changes the pressed state, redrawing the button if it changed.
+    fn set_pressed(&mut self, pressed: bool) {
+        if self.selected_state != pressed {
+            self.selected_state = pressed;
+            self.set_invalidated(true);
+        }
+    }
+
+    /// Changes the text displayed inside the button, invalidating the button so that it is redrawn.
     pub fn set_text(&mut self, msg: String) {
-        self.text_widget.set_text(msg.clone());
+        self.text_widget.set_text(msg);
+        self.set_invalidated(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_widget::BaseWidget;
+    use crate::cache::WidgetCache;
+    use crate::widget::SystemWidget;
+    use sdl2::event::Event;
+    use sdl2::mouse::MouseState;
+
+    /// Creates a `WidgetCache` containing a 50x50 button at `10,10`, returning the cache and the
+    /// ID of the button.  The font is only loaded when the button is drawn, so it does not need
+    /// to exist.
+    fn button_cache() -> (WidgetCache, WidgetId) {
+        let mut cache = WidgetCache::new();
+
+        cache.add(SystemWidget::Base(Box::new(BaseWidget::new(origin_point(), Size::new(200, 200)))));
+
+        let button_id = cache.add(SystemWidget::Button(Box::new(ButtonWidget::new(
+            Point::new(10, 10),
+            Size::new(50, 50),
+            String::from("assets/OpenSans-Regular.ttf"),
+            FontStyle::NORMAL,
+            14,
+            Color::BLACK,
+            TextJustify::Center,
+            String::from("Button"),
+            1,
+        ))));
+
+        (cache, button_id)
+    }
+
+    /// Creates a `MouseMotion` event with the pointer at `x,y`.
+    fn mouse_motion(x: i32, y: i32) -> Event {
+        Event::MouseMotion {
+            timestamp: 0,
+            window_id: 1,
+            which: 0,
+            mousestate: MouseState::from_sdl_state(0),
+            x,
+            y,
+            xrel: 0,
+            yrel: 0,
+        }
+    }
+
+    /// Creates a left mouse button event at `x,y`, pressed if `down` is set, released otherwise.
+    fn mouse_button(down: bool, x: i32, y: i32, clicks: u8) -> Event {
+        if down {
+            Event::MouseButtonDown {
+                timestamp: 0,
+                window_id: 1,
+                which: 0,
+                mouse_btn: MouseButton::Left,
+                clicks,
+                x,
+                y,
+            }
+        } else {
+            Event::MouseButtonUp {
+                timestamp: 0,
+                window_id: 1,
+                which: 0,
+                mouse_btn: MouseButton::Left,
+                clicks,
+                x,
+                y,
+            }
+        }
+    }
+
+    /// Returns the click count of the first `Clicked` event in `events`, if any.
+    fn clicked(events: &[PushrodEvent]) -> Option<(WidgetId, u8)> {
+        events.iter().find_map(|x| match x {
+            PushrodEvent::Clicked(widget_id, clicks) => Some((*widget_id, *clicks)),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_press_and_release_clicks() {
+        let (mut cache, button_id) = button_cache();
+
+        cache.handle_event(mouse_motion(20, 20));
+        assert_eq!(clicked(&cache.handle_event(mouse_button(true, 20, 20, 1))), None);
+        assert_eq!(clicked(&cache.handle_event(mouse_button(false, 20, 20, 1))), Some((button_id, 1)));
+
+        // The click count reported by `SDL2` is passed through for double-clicks.
+        cache.handle_event(mouse_button(true, 20, 20, 2));
+
+        assert_eq!(clicked(&cache.handle_event(mouse_button(false, 20, 20, 2))), Some((button_id, 2)));
+    }
+
+    #[test]
+    fn test_leaving_bounds_cancels_click() {
+        let (mut cache, _) = button_cache();
+
+        cache.handle_event(mouse_motion(20, 20));
+        cache.handle_event(mouse_button(true, 20, 20, 1));
+
+        // Leaving the button sends it a `BoundsChange`, which cancels the press, so returning to
+        // the button before releasing does not click it.
+        cache.handle_event(mouse_motion(100, 100));
+        cache.handle_event(mouse_motion(20, 20));
+
+        assert_eq!(clicked(&cache.handle_event(mouse_button(false, 20, 20, 1))), None);
+    }
+
+    #[test]
+    fn test_release_without_press() {
+        let (mut cache, _) = button_cache();
+
+        // The mouse button was pressed outside of the button, and released over it.
+        cache.handle_event(mouse_motion(100, 100));
+        cache.handle_event(mouse_button(true, 100, 100, 1));
+        cache.handle_event(mouse_motion(20, 20));
+
+        assert_eq!(clicked(&cache.handle_event(mouse_button(false, 20, 20, 1))), None);
+    }
+
+    #[test]
+    fn test_set_text_invalidates() {
+        let (mut cache, button_id) = button_cache();
+        let button = cache
+            .get_mut(button_id)
+            .and_then(|x| x.as_widget_mut().as_any_mut().downcast_mut::<ButtonWidget>())
+            .unwrap();
+
+        button.set_invalidated(false);
+        button.set_text(String::from("Relabelled"));
+
+        assert!(button.is_invalidated());
+    }
+
+    #[test]
+    fn test_border_wider_than_button() {
+        let button = ButtonWidget::new(
+            Point::new(0, 0),
+            Size::new(4, 4),
+            String::from("assets/OpenSans-Regular.ttf"),
+            FontStyle::NORMAL,
+            14,
+            Color::BLACK,
+            TextJustify::Center,
+            String::from("Button"),
+            3,
+        );
+
+        assert_eq!(*button.text_widget.get_size(), Size::new(0, 0));
     }
 }
@@ -16,21 +16,88 @@
 //! # WidgetCache
 //!
 //! Contains a cache of the `Widget`s that are members of a display `Window`.  `Widget`s are
-//! stored in the order of creation.
-
-use crate::event::PushrodEvent;
-use crate::geometry::make_rect;
-use crate::widget::{SystemWidget, Widget};
-use sdl2::event::Event;
-use sdl2::render::Canvas;
+//! stored in the order of creation, and are organized as a tree: every `Widget` other than the
+//! top-level `Widget` (ID 0) has a parent.
+//!
+//! The origin of a child `Widget` is relative to the origin of its parent, and children are
+//! clipped to the bounds of their parents when drawn.  Moving, hiding or disabling a parent
+//! `Widget` affects its entire subtree.  `Widget`s are drawn in tree order: a parent is drawn before its
+//! children, and children are drawn in order of their Z index.
+//!
+//! The window contents are retained in a scene texture.  Each `Widget` only redraws its own
+//! texture when it is invalidated, and the areas of the window that have changed are tracked as
+//! damaged rectangles.  The draw loop recomposes every `Widget` that intersects a damaged
+//! rectangle from its cached texture, in tree order, so that overlapping `Widget`s are repainted
+//! correctly, and untouched areas of the window are left as they are.  Invalidating a `Widget`,
+//! or moving, resizing, hiding, reordering or removing it through the `WidgetCache`, damages
+//! the area it occupies.  When the window is exposed, shown or restored, the retained scene is
+//! copied to the window and presented again, without recomposing any `Widget`s.
+//!
+//! Every `Widget` has a Z index, which orders it among its siblings: children with a higher Z
+//! index are drawn on top of, and are hit-tested before, children with a lower Z index.  Children
+//! with the same Z index are ordered by insertion.  Z indexes are only compared between siblings,
+//! so raising a `Widget` to the top raises it above its siblings, along with its entire subtree.
+//!
+//! At most one `Widget` has keyboard focus at a time.  Keyboard and text input events are sent to
+//! the focused `Widget`, rather than the `Widget` under the mouse pointer.  Focus is moved by
+//! clicking on a focusable `Widget`, or with `Tab` and `Shift-Tab`, which traverse the tab order.
+//! The focused `Widget` is drawn with a focus ring, and is sent `FocusGained` and `FocusLost`
+//! events as focus changes.
+
+use crate::error::PushrodError;
+use crate::event::{EventContext, PushrodEvent};
+use crate::geometry::{make_rect, Point, Size};
+use crate::widget::{SystemWidget, Widget, WidgetId};
+use log::{debug, error, trace};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseWheelDirection;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, Texture};
 use sdl2::video::Window;
 use crate::font::FontCache;
 
+/// Color of the overlay drawn over disabled `Widget`s, which dims their contents.
+const DISABLED_OVERLAY_COLOR: Color = Color::RGBA(128, 128, 128, 128);
+
+/// Color of the focus ring drawn inside the bounds of the focused `Widget`.
+const FOCUS_RING_COLOR: Color = Color::RGB(0, 120, 215);
+
+/// Width of the focus ring in pixels.
+const FOCUS_RING_WIDTH: u32 = 2;
+
+/// A `Widget` stored in the cache, along with its position in the tree of `Widget`s.
+struct WidgetEntry {
+    widget: SystemWidget,
+    parent: WidgetId,
+    children: Vec<WidgetId>,
+    z: i32,
+}
+
+/// A slot in the cache.  The generation is incremented each time the `Widget` stored in the slot
+/// is removed, so that `WidgetId`s referring to the removed `Widget` can no longer be used to
+/// access the slot once it is recycled.
+struct WidgetSlot {
+    generation: u32,
+    entry: Option<WidgetEntry>,
+}
+
 /// Contains a list of the `Widget`s in a `Vec`.  The `current_widget_id` indicates the currently
-/// active `Widget` ID under which the mouse pointer has located.
+/// active `Widget` ID under which the mouse pointer has located.  The `focused_widget_id`
+/// indicates the `Widget` that currently has keyboard focus, if any, and the `tab_order` is the
+/// order in which `Tab` moves focus between `Widget`s.  The `damage` list contains the areas of
+/// the window that need to be recomposed into the retained `scene` texture, and `exposed`
+/// indicates that the `scene` needs to be presented again, even if nothing was damaged.
 pub struct WidgetCache {
-    cache: Vec<SystemWidget>,
-    current_widget_id: u32,
+    cache: Vec<WidgetSlot>,
+    free_slots: Vec<u32>,
+    current_widget_id: WidgetId,
+    focused_widget_id: Option<WidgetId>,
+    tab_order: Vec<WidgetId>,
+    damage: Vec<Rect>,
+    scene: Option<Texture>,
+    exposed: bool,
 }
 
 impl WidgetCache {
@@ -38,59 +105,665 @@ impl WidgetCache {
     pub fn new() -> Self {
         Self {
             cache: Vec::new(),
-            current_widget_id: 0,
+            free_slots: Vec::new(),
+            current_widget_id: WidgetId::default(),
+            focused_widget_id: None,
+            tab_order: Vec::new(),
+            damage: Vec::new(),
+            scene: None,
+            exposed: false,
         }
     }
 
-    /// Adds a `SystemWidget` to the cache, returning its ID after insertion.
-    pub fn add(&mut self, widget: SystemWidget) -> i32 {
-        self.cache.push(widget);
-        (self.cache.len() - 1) as i32
+    /// Adds a `SystemWidget` to the cache as a child of the top-level `Widget`, returning its ID
+    /// after insertion.  The first `Widget` added to the cache becomes the top-level `Widget`.
+    pub fn add(&mut self, widget: SystemWidget) -> WidgetId {
+        // The top-level `Widget` cannot be removed, so it always exists as a parent.
+        self.add_child(WidgetId::default(), widget)
+            .expect("[WidgetCache::add] Top-level widget does not exist.")
     }
 
-    /// Retrieves an optional reference to the `SystemWidget` object by ID, `None` if not found.
-    pub fn get(&self, widget: i32) -> Option<&SystemWidget> {
-        if widget > self.cache.len() as i32 {
+    /// Adds a `SystemWidget` to the cache as a child of the `Widget` with the `parent_id`
+    /// specified, returning its ID after insertion.  The origin of the `Widget` is relative to
+    /// the origin of its parent.  The ID is also assigned to the `Widget` itself.
+    ///
+    /// Slots freed by removed `Widget`s are reused, but the returned ID is never equal to the ID
+    /// of a removed `Widget`.  Returns an `InvalidWidget` error if the parent does not exist, in
+    /// which case the `Widget` is not added.
+    pub fn add_child(&mut self, parent_id: WidgetId, mut widget: SystemWidget) -> Result<WidgetId, PushrodError> {
+        // The first `Widget` added becomes the top-level `Widget`, and has no parent to check.
+        if !self.cache.is_empty() && !self.contains(parent_id) {
+            return Err(PushrodError::InvalidWidget(parent_id));
+        }
+
+        let widget_id = match self.free_slots.pop() {
+            Some(index) => WidgetId::new(index, self.cache[index as usize].generation),
+            None => {
+                self.cache.push(WidgetSlot {
+                    generation: 0,
+                    entry: None,
+                });
+
+                WidgetId::new(self.cache.len() as u32 - 1, 0)
+            }
+        };
+
+        // The top-level `Widget` is its own parent, and is never listed as a child.
+        let parent_id = if widget_id == WidgetId::default() {
+            widget_id
+        } else {
+            if let Some(parent) = self.entry_mut(parent_id) {
+                parent.children.push(widget_id);
+            }
+
+            parent_id
+        };
+
+        widget.as_widget_mut().set_id(widget_id);
+
+        self.cache[widget_id.get_index() as usize].entry = Some(WidgetEntry {
+            widget,
+            parent: parent_id,
+            children: Vec::new(),
+            z: 0,
+        });
+
+        if parent_id != widget_id {
+            self.sort_children_of(parent_id);
+        }
+
+        Ok(widget_id)
+    }
+
+    /// Removes a `Widget` from the cache, along with its entire subtree, returning the removed
+    /// `Widget`.  The area that the `Widget` occupied is damaged, so that it is recomposed.
+    /// Returns an `InvalidWidget` error if the `Widget` was not found, or is the top-level
+    /// `Widget`, which cannot be removed.
+    ///
+    /// The textures of every removed `Widget` are destroyed, releasing their GPU memory.  The
+    /// returned `Widget` is invalidated, so that it is redrawn if it is added again.
+    pub fn remove(&mut self, widget_id: WidgetId) -> Result<SystemWidget, PushrodError> {
+        let parent_id = self
+            .get_parent(widget_id)
+            .ok_or(PushrodError::InvalidWidget(widget_id))?;
+        let clip_rect = if self.is_visible(widget_id) {
+            self.get_clip_rect(widget_id)
+        } else {
+            None
+        };
+
+        if let Some(parent) = self.entry_mut(parent_id) {
+            parent.children.retain(|x| *x != widget_id);
+        }
+
+        let removed = self.remove_subtree(widget_id);
+
+        if self.entry(self.current_widget_id).is_none() {
+            self.current_widget_id = WidgetId::default();
+        }
+
+        if self.focused_widget_id.is_some_and(|x| !self.contains(x)) {
+            self.focused_widget_id = None;
+        }
+
+        if let Some(clip_rect) = clip_rect {
+            self.invalidate_rect(clip_rect);
+        }
+
+        removed.ok_or(PushrodError::InvalidWidget(widget_id))
+    }
+
+    /// Internal function that removes a `Widget` and all of its children from their slots,
+    /// freeing the slots for reuse, and destroying their textures.
+    fn remove_subtree(&mut self, widget_id: WidgetId) -> Option<SystemWidget> {
+        let slot = &mut self.cache[widget_id.get_index() as usize];
+        let mut entry = slot.entry.take()?;
+
+        entry.widget.as_widget_mut().destroy_textures();
+        entry.widget.as_widget_mut().set_invalidated(true);
+
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(widget_id.get_index());
+
+        for child_id in entry.children {
+            self.remove_subtree(child_id);
+        }
+
+        Some(entry.widget)
+    }
+
+    /// Indicates whether or not the `Widget` with the ID specified exists in the cache.
+    pub fn contains(&self, widget_id: WidgetId) -> bool {
+        self.entry(widget_id).is_some()
+    }
+
+    /// Internal function that retrieves the entry for a `Widget`, `None` if not found.
+    fn entry(&self, widget_id: WidgetId) -> Option<&WidgetEntry> {
+        let slot = self.cache.get(widget_id.get_index() as usize)?;
+
+        if slot.generation == widget_id.get_generation() {
+            slot.entry.as_ref()
+        } else {
             None
+        }
+    }
+
+    /// Internal function that retrieves the mutable entry for a `Widget`, `None` if not found.
+    fn entry_mut(&mut self, widget_id: WidgetId) -> Option<&mut WidgetEntry> {
+        let slot = self.cache.get_mut(widget_id.get_index() as usize)?;
+
+        if slot.generation == widget_id.get_generation() {
+            slot.entry.as_mut()
         } else {
-            Some(&self.cache[widget as usize])
+            None
         }
     }
 
-    /// Retrieves the next available `Widget` cache ID.
-    pub fn get_current_widget(&self) -> u32 {
+    /// Retrieves an optional reference to the `SystemWidget` object by ID, `None` if not found.
+    pub fn get(&self, widget_id: WidgetId) -> Option<&SystemWidget> {
+        self.entry(widget_id).map(|x| &x.widget)
+    }
+
+    /// Retrieves an optional mutable reference to the `SystemWidget` object by ID, `None` if not
+    /// found.  Changes to the origin, size or visibility of the `Widget` made through this
+    /// reference do not damage the area it occupies, so use `set_origin`, `set_size` and
+    /// `set_visible` instead.
+    pub fn get_mut(&mut self, widget_id: WidgetId) -> Option<&mut SystemWidget> {
+        self.entry_mut(widget_id).map(|x| &mut x.widget)
+    }
+
+    /// Retrieves the ID of the parent of a `Widget`.  Returns `None` for the top-level `Widget`,
+    /// or if the `Widget` was not found.
+    pub fn get_parent(&self, widget_id: WidgetId) -> Option<WidgetId> {
+        match self.entry(widget_id) {
+            Some(entry) if entry.parent != widget_id => Some(entry.parent),
+            _ => None,
+        }
+    }
+
+    /// Retrieves the IDs of the direct children of a `Widget`, in drawing order, from bottom to
+    /// top.
+    pub fn get_children_of(&self, widget_id: WidgetId) -> Vec<WidgetId> {
+        match self.entry(widget_id) {
+            Some(entry) => entry.children.clone(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Retrieves the ID of the `Widget` under which the mouse pointer is currently located.
+    pub fn get_current_widget(&self) -> WidgetId {
         self.current_widget_id
     }
 
-    /// Internal function that sends a `PushrodEvent` to a widget, and captures the returned event.
-    fn send_and_receive_event_to_widget(
-        &self,
-        widget_id: u32,
-        event: PushrodEvent,
-    ) -> Option<&[PushrodEvent]> {
-        match &self.cache[widget_id as usize] {
-            SystemWidget::Base(x) => {
-                return x.handle_event(event);
+    /// Computes the origin of a `Widget` in `Window` coordinates, by adding the origins of all
+    /// of its parents to its own.  Returns `None` if the `Widget` was not found.
+    pub fn get_absolute_origin(&self, widget_id: WidgetId) -> Option<Point> {
+        let mut entry = self.entry(widget_id)?;
+        let mut widget_id = widget_id;
+        let mut origin = Point::new(0, 0);
+
+        loop {
+            let widget_origin = entry.widget.as_widget().get_origin();
+
+            origin.x += widget_origin.x;
+            origin.y += widget_origin.y;
+
+            if entry.parent == widget_id {
+                return Some(origin);
             }
 
-            SystemWidget::Box(x) => {
-                return x.handle_event(event);
+            widget_id = entry.parent;
+            entry = self.entry(widget_id)?;
+        }
+    }
+
+    /// Computes the bounds of a `Widget` in `Window` coordinates.  Returns `None` if the `Widget`
+    /// was not found.
+    pub fn get_absolute_rect(&self, widget_id: WidgetId) -> Option<Rect> {
+        let size = *self.entry(widget_id)?.widget.as_widget().get_size();
+
+        Some(make_rect(self.get_absolute_origin(widget_id)?, size))
+    }
+
+    /// Computes the area in `Window` coordinates that a `Widget` may draw into: the intersection
+    /// of its own bounds with the bounds of all of its parents.  Returns `None` if the `Widget`
+    /// lies entirely outside of the bounds of one of its parents, or was not found.
+    pub fn get_clip_rect(&self, widget_id: WidgetId) -> Option<Rect> {
+        let mut clip = self.get_absolute_rect(widget_id)?;
+        let mut widget_id = widget_id;
+
+        while let Some(parent_id) = self.get_parent(widget_id) {
+            clip = clip.intersection(self.get_absolute_rect(parent_id)?)?;
+            widget_id = parent_id;
+        }
+
+        Some(clip)
+    }
+
+    /// Moves a `Widget` to a new origin, relative to its parent.  Its children move along with it.
+    /// Both the area the `Widget` previously occupied, and the area it now occupies, are damaged.
+    /// Returns an `InvalidWidget` error if the `Widget` does not exist.
+    pub fn set_origin(&mut self, widget_id: WidgetId, origin: Point) -> Result<(), PushrodError> {
+        trace!(target: "pushrod::layout", "Moving widget {}: origin={},{}", widget_id, origin.x, origin.y);
+
+        self.damage_widget(widget_id);
+        self.get_widget_mut_or_err(widget_id)?.set_origin(origin);
+        self.damage_widget(widget_id);
+
+        Ok(())
+    }
+
+    /// Resizes a `Widget`.  Both the area the `Widget` previously occupied, and the area it now
+    /// occupies, are damaged.  The `Widget` is invalidated, so that it is redrawn at its new size.
+    /// Returns an `InvalidWidget` error if the `Widget` does not exist.
+    pub fn set_size(&mut self, widget_id: WidgetId, size: Size) -> Result<(), PushrodError> {
+        trace!(target: "pushrod::layout", "Resizing widget {}: size={}x{}", widget_id, size.w, size.h);
+
+        self.damage_widget(widget_id);
+
+        let widget = self.get_widget_mut_or_err(widget_id)?;
+
+        widget.set_size(size);
+        widget.set_invalidated(true);
+        self.damage_widget(widget_id);
+
+        Ok(())
+    }
+
+    /// Hides or shows a `Widget`, along with its entire subtree.  The area the `Widget` occupies is
+    /// damaged, so that it is recomposed with or without the `Widget`.  Returns an `InvalidWidget`
+    /// error if the `Widget` does not exist.
+    pub fn set_visible(&mut self, widget_id: WidgetId, visible: bool) -> Result<(), PushrodError> {
+        self.get_widget_mut_or_err(widget_id)?.set_visible(visible);
+        self.damage_widget(widget_id);
+
+        Ok(())
+    }
+
+    /// Indicates whether or not a `Widget` is visible.  A `Widget` is only visible if it and all
+    /// of its parents are visible.  `Widget`s that were not found are not visible.
+    pub fn is_visible(&self, widget_id: WidgetId) -> bool {
+        self.all_ancestors(widget_id, |x| x.is_visible())
+    }
+
+    /// Enables or disables a `Widget`, along with its entire subtree.  The `Widget` is invalidated,
+    /// so that it is redrawn with or without the disabled overlay.  Disabling a `Widget` cancels
+    /// any interaction in progress in its subtree, such as a held button.  Returns an
+    /// `InvalidWidget` error if the `Widget` does not exist.
+    pub fn set_enabled(&mut self, widget_id: WidgetId, enabled: bool) -> Result<(), PushrodError> {
+        self.get_widget_mut_or_err(widget_id)?.set_enabled(enabled);
+
+        if !enabled {
+            self.cancel_interactions(widget_id);
+        }
+
+        Ok(())
+    }
+
+    /// Internal function that cancels any interaction in progress for a `Widget`, and each of its
+    /// children.
+    fn cancel_interactions(&mut self, widget_id: WidgetId) {
+        if let Some(entry) = self.entry_mut(widget_id) {
+            entry.widget.as_widget_mut().cancel_interaction();
+
+            for child_id in entry.children.clone() {
+                self.cancel_interactions(child_id);
             }
+        }
+    }
+
+    /// Indicates whether or not a `Widget` is enabled.  A `Widget` is only enabled if it and all
+    /// of its parents are enabled.  `Widget`s that were not found are not enabled.
+    pub fn is_enabled(&self, widget_id: WidgetId) -> bool {
+        self.all_ancestors(widget_id, |x| x.is_enabled())
+    }
+
+    /// Internal function that checks a condition against a `Widget`, and each of its parents,
+    /// returning `true` only if all of them satisfy it.  Returns `false` if the `Widget` was not
+    /// found.
+    fn all_ancestors<F: Fn(&dyn Widget) -> bool>(&self, widget_id: WidgetId, condition: F) -> bool {
+        let mut widget_id = widget_id;
 
-            SystemWidget::Text(x) => {
-                return x.handle_event(event);
+        loop {
+            match self.entry(widget_id) {
+                Some(entry) if condition(entry.widget.as_widget()) => {}
+                _ => return false,
             }
 
-            SystemWidget::Button(x) => {
-                return x.handle_event(event);
+            match self.get_parent(widget_id) {
+                Some(parent_id) => widget_id = parent_id,
+                None => return true,
             }
+        }
+    }
+
+    /// Retrieves the Z index of a `Widget`, `None` if not found.
+    pub fn get_z(&self, widget_id: WidgetId) -> Option<i32> {
+        self.entry(widget_id).map(|x| x.z)
+    }
+
+    /// Sets the Z index of a `Widget`, reordering it among its siblings.  If siblings share the
+    /// same Z index, their existing order is preserved.  Returns an `InvalidWidget` error if the
+    /// `Widget` does not exist.
+    pub fn set_z(&mut self, widget_id: WidgetId, z: i32) -> Result<(), PushrodError> {
+        let entry = self
+            .entry_mut(widget_id)
+            .ok_or(PushrodError::InvalidWidget(widget_id))?;
+
+        entry.z = z;
+
+        if let Some(parent_id) = self.get_parent(widget_id) {
+            self.sort_children_of(parent_id);
+        }
+
+        self.damage_widget(widget_id);
 
-            _unused => {
-                panic!("[WidgetCache::send_and_receive_event_to_widget] I am trying to handle an event with a widget that I can't handle yet!");
+        Ok(())
+    }
+
+    /// Raises a `Widget` above all of its siblings.  Returns an `InvalidWidget` error if the
+    /// `Widget` does not exist.
+    pub fn raise_to_top(&mut self, widget_id: WidgetId) -> Result<(), PushrodError> {
+        self.get_widget_mut_or_err(widget_id)?;
+
+        let top_z = self
+            .get_siblings_of(widget_id)
+            .filter_map(|x| self.get_z(x))
+            .max();
+
+        match top_z {
+            Some(top_z) => self.set_z(widget_id, top_z.saturating_add(1)),
+            None => Ok(()),
+        }
+    }
+
+    /// Lowers a `Widget` below all of its siblings.  Returns an `InvalidWidget` error if the
+    /// `Widget` does not exist.
+    pub fn lower_to_bottom(&mut self, widget_id: WidgetId) -> Result<(), PushrodError> {
+        self.get_widget_mut_or_err(widget_id)?;
+
+        let bottom_z = self
+            .get_siblings_of(widget_id)
+            .filter_map(|x| self.get_z(x))
+            .min();
+
+        match bottom_z {
+            Some(bottom_z) => self.set_z(widget_id, bottom_z.saturating_sub(1)),
+            None => Ok(()),
+        }
+    }
+
+    /// Internal function that retrieves a `Widget` by ID, returning an `InvalidWidget` error if it
+    /// does not exist.
+    fn get_widget_mut_or_err(&mut self, widget_id: WidgetId) -> Result<&mut dyn Widget, PushrodError> {
+        self.entry_mut(widget_id)
+            .map(|x| x.widget.as_widget_mut())
+            .ok_or(PushrodError::InvalidWidget(widget_id))
+    }
+
+    /// Returns the IDs of the other children of the parent of a `Widget`.
+    fn get_siblings_of(&self, widget_id: WidgetId) -> impl Iterator<Item = WidgetId> + '_ {
+        let siblings = match self.get_parent(widget_id).and_then(|x| self.entry(x)) {
+            Some(parent) => parent.children.as_slice(),
+            None => &[],
+        };
+
+        siblings.iter().copied().filter(move |x| *x != widget_id)
+    }
+
+    /// Re-sorts the children of a `Widget` by Z index.  The sort is stable, so children with the
+    /// same Z index keep their existing order.
+    fn sort_children_of(&mut self, widget_id: WidgetId) {
+        if let Some(entry) = self.entry_mut(widget_id) {
+            let mut children = std::mem::take(&mut entry.children);
+
+            children.sort_by_key(|x| self.get_z(*x).unwrap_or(0));
+
+            if let Some(entry) = self.entry_mut(widget_id) {
+                entry.children = children;
+            }
+        }
+    }
+
+    /// Damages the visible area of a `Widget`, so that it is recomposed during the next draw loop.
+    pub(crate) fn damage_widget(&mut self, widget_id: WidgetId) {
+        if let Some(clip_rect) = self.get_clip_rect(widget_id) {
+            self.invalidate_rect(clip_rect);
+        }
+    }
+
+    /// Damages the area specified in `Window` coordinates, so that every `Widget` intersecting
+    /// it is recomposed from its cached texture during the next draw loop.  The `Widget`s
+    /// themselves are not redrawn, unless they are invalidated.
+    pub fn invalidate_rect(&mut self, rect: Rect) {
+        self.damage.push(rect);
+    }
+
+    /// Invalidates every `Widget` in the cache, forcing the entire cache to be redrawn during the
+    /// next draw loop.
+    pub fn invalidate_all(&mut self) {
+        for entry in self.cache.iter_mut().filter_map(|x| x.entry.as_mut()) {
+            entry.widget.as_widget_mut().set_invalidated(true);
+        }
+    }
+
+    /// Returns the IDs of every `Widget` in the cache, in order of their slots.
+    pub fn get_widget_ids(&self) -> impl Iterator<Item = WidgetId> + '_ {
+        self.cache
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.entry.is_some())
+            .map(|(index, slot)| WidgetId::new(index as u32, slot.generation))
+    }
+
+    /// Retrieves the ID of the `Widget` that currently has keyboard focus, if any.
+    pub fn get_focused_widget(&self) -> Option<WidgetId> {
+        self.focused_widget_id
+    }
+
+    /// Moves keyboard focus to the `Widget` specified, or clears focus if `None`.  The `Widget`
+    /// losing focus is sent a `FocusLost` event, and the `Widget` gaining focus is sent a
+    /// `FocusGained` event.  The areas of both `Widget`s are damaged so that the focus ring is
+    /// redrawn.
+    ///
+    /// Returns the focus events, along with any events generated by the `Widget`s in response.
+    /// Focus is not moved to `Widget`s that cannot be focused, are hidden, or are disabled.
+    pub fn set_focused_widget(&mut self, widget_id: Option<WidgetId>) -> Vec<PushrodEvent> {
+        let mut return_vector = Vec::new();
+
+        if widget_id == self.focused_widget_id || widget_id.is_some_and(|x| !self.can_focus(x)) {
+            return return_vector;
+        }
+
+        let previous_widget_id = std::mem::replace(&mut self.focused_widget_id, widget_id);
+
+        debug!(target: "pushrod::event", "Focus moved: from={:?} to={:?}", previous_widget_id, widget_id);
+
+        for (widget_id, event) in [
+            (previous_widget_id, previous_widget_id.map(PushrodEvent::FocusLost)),
+            (widget_id, widget_id.map(PushrodEvent::FocusGained)),
+        ] {
+            if let (Some(widget_id), Some(event)) = (widget_id, event) {
+                self.damage_widget(widget_id);
+
+                return_vector.push(event.clone());
+
+                return_vector.extend(self.send_and_receive_event_to_widget(widget_id, event));
+            }
+        }
+
+        return_vector
+    }
+
+    /// Indicates whether or not a `Widget` can currently receive focus: it must be focusable,
+    /// visible and enabled.
+    fn can_focus(&self, widget_id: WidgetId) -> bool {
+        self.entry(widget_id)
+            .is_some_and(|x| x.widget.as_widget().is_focusable())
+            && self.is_visible(widget_id)
+            && self.is_enabled(widget_id)
+    }
+
+    /// Sets the order in which `Tab` moves focus between `Widget`s.  `Shift-Tab` moves through the
+    /// order in reverse.  If the order is empty (the default), all focusable `Widget`s are
+    /// traversed in tree order.
+    pub fn set_tab_order(&mut self, tab_order: Vec<WidgetId>) {
+        self.tab_order = tab_order;
+    }
+
+    /// Retrieves the order in which `Tab` moves focus between `Widget`s.  If no order was set,
+    /// this is every focusable `Widget` in tree order.  `Widget`s that no longer exist are omitted.
+    pub fn get_tab_order(&self) -> Vec<WidgetId> {
+        if self.tab_order.is_empty() {
+            let mut tab_order = Vec::new();
+
+            if !self.cache.is_empty() {
+                self.collect_focusable(WidgetId::default(), &mut tab_order);
             }
+
+            tab_order
+        } else {
+            self.tab_order
+                .iter()
+                .copied()
+                .filter(|x| self.contains(*x))
+                .collect()
         }
     }
 
+    /// Internal function that collects the focusable `Widget`s in a subtree, in tree order.
+    fn collect_focusable(&self, widget_id: WidgetId, tab_order: &mut Vec<WidgetId>) {
+        if let Some(entry) = self.entry(widget_id) {
+            if entry.widget.as_widget().is_focusable() {
+                tab_order.push(widget_id);
+            }
+
+            for child_id in entry.children.iter() {
+                self.collect_focusable(*child_id, tab_order);
+            }
+        }
+    }
+
+    /// Moves focus to the next `Widget` in the tab order that can receive focus, or the previous
+    /// `Widget` if `reverse` is set, wrapping around at either end.  Returns the generated focus
+    /// events.
+    pub fn focus_next(&mut self, reverse: bool) -> Vec<PushrodEvent> {
+        let mut tab_order = self.get_tab_order();
+
+        if reverse {
+            tab_order.reverse();
+        }
+
+        // Start searching after the focused `Widget`, or from the start of the order if no
+        // `Widget` in the order is focused.
+        let start = self
+            .focused_widget_id
+            .and_then(|focused| tab_order.iter().position(|x| *x == focused))
+            .map_or(0, |x| x + 1);
+        let next_widget_id = tab_order
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(tab_order.len())
+            .copied()
+            .find(|x| self.can_focus(*x));
+
+        match next_widget_id {
+            Some(widget_id) => self.set_focused_widget(Some(widget_id)),
+            None => Vec::new(),
+        }
+    }
+
+    /// Internal function that translates an `SDL2` keyboard or text input `Event` into the
+    /// equivalent `PushrodEvent` for the focused `Widget`.  Returns `None` for any other `Event`.
+    fn translate_focus_event(widget_id: WidgetId, event: Event) -> Option<PushrodEvent> {
+        match event {
+            Event::KeyDown {
+                keycode,
+                scancode,
+                keymod,
+                repeat,
+                ..
+            } => Some(PushrodEvent::KeyDown {
+                widget_id,
+                keycode,
+                scancode,
+                keymod,
+                repeat,
+            }),
+
+            Event::KeyUp {
+                keycode,
+                scancode,
+                keymod,
+                repeat,
+                ..
+            } => Some(PushrodEvent::KeyUp {
+                widget_id,
+                keycode,
+                scancode,
+                keymod,
+                repeat,
+            }),
+
+            Event::TextInput { text, .. } => Some(PushrodEvent::TextInput(widget_id, text)),
+
+            Event::TextEditing {
+                text, start, length, ..
+            } => Some(PushrodEvent::TextEditing {
+                widget_id,
+                text,
+                start,
+                length,
+            }),
+
+            _ => None,
+        }
+    }
+
+    /// Sends a `PushrodEvent` directly to a `Widget`, such as a `Custom` event addressed to a
+    /// specific `Widget`.  Returns the events generated by the `Widget` in response.  Events sent
+    /// to `Widget`s that do not exist, or that are disabled, are discarded.
+    pub fn send_event(&mut self, widget_id: WidgetId, event: PushrodEvent) -> Vec<PushrodEvent> {
+        self.send_and_receive_event_to_widget(widget_id, event)
+    }
+
+    /// Internal function that sends a `PushrodEvent` to a widget, and returns the events it
+    /// generated in response.  Events sent to `Widget`s that no longer exist, or that are
+    /// disabled, are discarded.
+    ///
+    /// The `Widget`'s entry is taken out of the cache while it handles the event, so that the
+    /// `Widget` can be borrowed mutably while the `EventContext` refers to the rest of the cache.
+    /// Any redraws requested through the `EventContext` are applied once the `Widget` returns.
+    fn send_and_receive_event_to_widget(&mut self, widget_id: WidgetId, event: PushrodEvent) -> Vec<PushrodEvent> {
+        // Disabled `Widget`s include `Widget`s that do not exist, so the entry is guaranteed to be
+        // present in its slot after this check.
+        if !self.is_enabled(widget_id) {
+            return Vec::new();
+        }
+
+        let Some(mut entry) = self.cache[widget_id.get_index() as usize].entry.take() else {
+            return Vec::new();
+        };
+        let mut context = EventContext::new(widget_id, self);
+
+        trace!(target: "pushrod::event", "Sending event to widget {}: {:?}", widget_id, event);
+
+        entry.widget.as_widget_mut().handle_event(event, &mut context);
+
+        let (events, redraw_widget_ids) = context.into_parts();
+
+        self.cache[widget_id.get_index() as usize].entry = Some(entry);
+
+        for redraw_widget_id in redraw_widget_ids {
+            if let Some(entry) = self.entry_mut(redraw_widget_id) {
+                entry.widget.as_widget_mut().set_invalidated(true);
+            }
+        }
+
+        events
+    }
+
     /// This handles the direct events from the `Engine`.  Raw events are sent in from the
     /// `event_pump` from the `SDL2` library, are translated, and are returned as a `PushrodEvent`
     /// list after being processed.  Any `Widget` that takes in an event, processes it, and generates
@@ -104,68 +777,38 @@ impl WidgetCache {
     /// - `Event` is deconstructed, handled for `Widget`s in the cache, and sent to the `Widget` using
     ///   `send_and_receive_event_to_widget`
     /// - `PushrodEvent(s)` returned from the function are then yielded back to the `Engine`.
-    pub fn handle_event(&mut self, event: Event) -> Vec<&PushrodEvent> {
-        // This is our return list of `PushrodEvent` references that are sent back to the
-        // `Engine` for processing by the `handle_event` function that may or may not have been
-        // set in the `Engine` at runtime.
-        let mut return_vector: Vec<&PushrodEvent> = Vec::new();
+    ///
+    /// Mouse events are sent to the `Widget` under the mouse pointer, while keyboard and text
+    /// input events are sent to the `Widget` with keyboard focus.  Keyboard, text input, mouse
+    /// wheel and window events are translated into their typed `PushrodEvent` equivalents, rather
+    /// than being wrapped in a `SystemEvent`.
+    pub fn handle_event(&mut self, event: Event) -> Vec<PushrodEvent> {
+        // This is our return list of `PushrodEvent`s that are sent back to the `Engine` for
+        // processing by the `handle_event` function that may or may not have been set in the
+        // `Engine` at runtime.
+        let mut return_vector: Vec<PushrodEvent> = Vec::new();
 
         // Main event match
         match event {
-            Event::MouseButtonDown {
-                mouse_btn,
-                clicks,
-                x,
-                y,
-                ..
-            } => {
+            // Clicking a focusable `Widget` moves focus to it, before the click is delivered.
+            Event::MouseButtonDown { x, y, .. } | Event::MouseButtonUp { x, y, .. } => {
                 self.current_widget_id = self.get_widget_id(x, y);
 
-                if let Some(x) = self.send_and_receive_event_to_widget(
-                    self.current_widget_id,
-                    PushrodEvent::SystemEvent(self.current_widget_id, event.clone()),
-                ) {
-                    for i in 0..x.len() {
-                        return_vector.push(&x[i]);
-                    }
+                if matches!(event, Event::MouseButtonDown { .. }) && self.can_focus(self.current_widget_id) {
+                    return_vector.extend(self.set_focused_widget(Some(self.current_widget_id)));
                 }
-            }
 
-            Event::MouseButtonUp {
-                mouse_btn,
-                clicks,
-                x,
-                y,
-                ..
-            } => {
-                self.current_widget_id = self.get_widget_id(x, y);
-
-                if let Some(x) = self.send_and_receive_event_to_widget(
+                return_vector.extend(self.send_and_receive_event_to_widget(
                     self.current_widget_id,
                     PushrodEvent::SystemEvent(self.current_widget_id, event.clone()),
-                ) {
-                    for i in 0..x.len() {
-                        return_vector.push(&x[i]);
-                    }
-                }
+                ));
             }
 
             // Handles a `MouseMotion` event, capturing the timestamp, UI window ID, mouse button ID,
             // the mouse state (down, up), `X` and `Y` coordinates relative to the `Window`, and the
             // `xrel` and `yrel` relative values from the previous and current `x` and `y` mouse
             // states.
-            Event::MouseMotion {
-                timestamp,
-                window_id,
-                which,
-                mousestate,
-                x,
-                y,
-                xrel,
-                yrel,
-            } => {
-                let mut x_offset = 0;
-                let mut y_offset = 0;
+            Event::MouseMotion { x, y, .. } => {
                 let previous_widget_id = self.current_widget_id;
 
                 // Retrieve the top-most widget ID located within the bounds of the `X` and `Y`
@@ -178,63 +821,97 @@ impl WidgetCache {
                 if self.current_widget_id != previous_widget_id {
                     let bounds_event = PushrodEvent::BoundsChange(previous_widget_id, self.current_widget_id);
 
-                    if let Some(x) =
-                        self.send_and_receive_event_to_widget(previous_widget_id, bounds_event.clone())
-                    {
-                        for i in 0..x.len() {
-                            return_vector.push(&x[i]);
-                        }
-                    }
+                    return_vector.extend(self.send_and_receive_event_to_widget(previous_widget_id, bounds_event.clone()));
 
-                    if let Some(x) =
-                        self.send_and_receive_event_to_widget(self.current_widget_id, bounds_event.clone())
-                    {
-                        for i in 0..x.len() {
-                            return_vector.push(&x[i]);
-                        }
-                    }
+                    return_vector.extend(self.send_and_receive_event_to_widget(self.current_widget_id, bounds_event));
                 }
 
-                // Determine the offset of the X and Y coordinates within the `Widget` where the
-                // mouse is currently located in relation to its bounds.
-                match &self.cache[self.current_widget_id as usize] {
-                    SystemWidget::Base(x) => {
-                        x_offset = x.get_origin().x;
-                        y_offset = x.get_origin().y;
-                    }
-
-                    SystemWidget::Box(x) => {
-                        x_offset = x.get_origin().x;
-                        y_offset = x.get_origin().y;
-                    }
+                // Wrap the event in a `SystemEvent`, and send it to the `Widget`.  If the `Widget`
+                // handles the event and generates its own, add any additional `Event`s generated
+                // to the list of return events.
+                return_vector.extend(self.send_and_receive_event_to_widget(
+                    self.current_widget_id,
+                    PushrodEvent::SystemEvent(self.current_widget_id, event.clone()),
+                ));
+            }
 
-                    SystemWidget::Text(x) => {
-                        x_offset = x.get_origin().x;
-                        y_offset = x.get_origin().y;
-                    }
+            // `Tab` and `Shift-Tab` move focus between `Widget`s, and are not delivered to the
+            // focused `Widget`.
+            Event::KeyDown {
+                keycode: Some(Keycode::Tab),
+                keymod,
+                ..
+            } => {
+                let reverse = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
 
-                    SystemWidget::Button(x) => {
-                        x_offset = x.get_origin().x;
-                        y_offset = x.get_origin().y;
-                    }
+                return_vector.extend(self.focus_next(reverse));
+            }
 
-                    _unused => {
-                        panic!(
-                            "[WidgetCache::handle_event] I am trying to handle an event with a widget that I can't handle yet!"
-                        );
+            Event::KeyUp {
+                keycode: Some(Keycode::Tab),
+                ..
+            } => {}
+
+            // All other keyboard and text input events are sent to the focused `Widget`.
+            Event::KeyDown { .. }
+            | Event::KeyUp { .. }
+            | Event::TextInput { .. }
+            | Event::TextEditing { .. } => {
+                // Hidden or disabled `Widget`s keep focus, but do not receive input until they
+                // can be focused again.
+                if let Some(focused_widget_id) = self.focused_widget_id.filter(|x| self.can_focus(*x)) {
+                    if let Some(focus_event) = Self::translate_focus_event(focused_widget_id, event) {
+                        return_vector.extend(self.send_and_receive_event_to_widget(focused_widget_id, focus_event));
                     }
                 }
+            }
 
-                // Wrap the event in a `SystemEvent`, and send it to the `Widget`.  If the `Widget`
-                // handles the event and generates its own, add any additional `Event`s generated
-                // to the list of return events.
-                if let Some(x) = self.send_and_receive_event_to_widget(
-                    self.current_widget_id,
-                    PushrodEvent::SystemEvent(self.current_widget_id, event.clone()),
-                ) {
-                    for i in 0..x.len() {
-                        return_vector.push(&x[i]);
+            // Mouse wheel events are sent to the `Widget` under the mouse pointer.  Flipped
+            // ("natural") scrolling is normalized, so that `Widget`s see the same direction
+            // regardless of the system setting.
+            Event::MouseWheel {
+                x,
+                y,
+                direction,
+                precise_x,
+                precise_y,
+                ..
+            } => {
+                let sign = if direction == MouseWheelDirection::Flipped { -1 } else { 1 };
+                let wheel_event = PushrodEvent::MouseWheel {
+                    widget_id: self.current_widget_id,
+                    x: x * sign,
+                    y: y * sign,
+                    precise_x: precise_x * sign as f32,
+                    precise_y: precise_y * sign as f32,
+                };
+
+                return_vector.extend(self.send_and_receive_event_to_widget(self.current_widget_id, wheel_event));
+            }
+
+            // Window events are sent to the top-level `Widget`, and are always passed on to the
+            // `Engine`, whether or not the top-level `Widget` handles them.
+            Event::Window { win_event, .. } => {
+                let window_event = match win_event {
+                    WindowEvent::SizeChanged(w, h) => Some(PushrodEvent::WindowResized(Size::new(
+                        w.max(0) as u32,
+                        h.max(0) as u32,
+                    ))),
+                    WindowEvent::FocusGained => Some(PushrodEvent::WindowFocusGained),
+                    WindowEvent::FocusLost => Some(PushrodEvent::WindowFocusLost),
+                    // The window contents may have been lost while it was covered, hidden or
+                    // minimized, so the retained scene is presented again.
+                    WindowEvent::Exposed | WindowEvent::Shown | WindowEvent::Restored => {
+                        self.exposed = true;
+                        None
                     }
+                    _ => None,
+                };
+
+                if let Some(window_event) = window_event {
+                    return_vector.push(window_event.clone());
+
+                    return_vector.extend(self.send_and_receive_event_to_widget(WidgetId::default(), window_event));
                 }
             }
 
@@ -244,241 +921,259 @@ impl WidgetCache {
         return_vector
     }
 
-    /// This is the main draw loop for all of the `Widget`s in the cache.  Since we are drawing
-    /// to a GPU texture, and not the screen directly, there is no need to compute overlapping
-    /// components.  Just call the draw method on any of the invalidated components, and let the
-    /// GPU blit them to the screen.  Returns `true` if any members of the cache need to be redrawn
-    /// to the screen by flipping the GPU texture cache, `false` indicating no change.
-    pub fn draw_loop(&mut self, c: &mut Canvas<Window>, fc: &mut FontCache) -> bool {
-        let mut invalidated = false;
-        let cache_size = self.cache.len();
-
-        // Walk the cache and only draw objects that are invalidated.
-        for i in 0..cache_size {
-            match &self.cache[i] {
-                SystemWidget::Base(x) => {
-                    if x.is_invalidated() {
-                        invalidated = true;
-                        self.draw(i as u32, c, fc);
-                    }
-                }
+    /// This is the main draw loop, which composes the `Widget`s into the retained scene, and
+    /// copies the scene to the canvas.  Returns `true` if anything changed, or the window was
+    /// exposed, indicating that the canvas needs to be presented, `false` indicating no change, or
+    /// an error if the scene could not be created or composed.
+    ///
+    /// First, each visible `Widget` that is invalidated redraws its own texture, and the area it
+    /// occupies is damaged.  Then, each damaged area of the scene is cleared, and every `Widget`
+    /// intersecting it is copied from its cached texture, in tree order, clipped to the bounds of
+    /// its parents.  Hidden `Widget`s, and their children, are not drawn.  Disabled `Widget`s are
+    /// drawn with a dimmed overlay, and the focused `Widget` is drawn with a focus ring.
+    ///
+    /// A `Widget` that fails to draw does not stop the draw loop.  The error is reported, and the
+    /// `Widget` keeps its previous texture, if any, until it is invalidated again.
+    ///
+    /// Nothing is composed while the top-level `Widget` has a zero width or height, as the scene
+    /// cannot be created.  The damaged areas are kept, and composed once it has a size again.
+    pub fn draw_loop(&mut self, c: &mut Canvas<Window>, fc: &mut FontCache) -> Result<bool, PushrodError> {
+        let size = match self.entry(WidgetId::default()) {
+            Some(entry) => *entry.widget.as_widget().get_size(),
+            None => return Ok(false),
+        };
+
+        if size.w == 0 || size.h == 0 {
+            return Ok(false);
+        }
 
-                SystemWidget::Box(x) => {
-                    if x.is_invalidated() {
-                        invalidated = true;
-                        self.draw(i as u32, c, fc);
-                    }
-                }
+        let mut damage = std::mem::take(&mut self.damage);
+        let exposed = std::mem::take(&mut self.exposed);
 
-                SystemWidget::Text(x) => {
-                    if x.is_invalidated() {
-                        invalidated = true;
-                        self.draw(i as u32, c, fc);
+        self.render_tree(WidgetId::default(), &mut damage, c, fc);
+
+        // The scene is recreated if the size of the top-level `Widget` has changed, in which case
+        // the entire scene is damaged.  Textures are not destroyed when dropped, so the previous
+        // scene is destroyed explicitly.
+        let mut scene = match self.scene.take() {
+            Some(scene) if scene.query().width == size.w && scene.query().height == size.h => scene,
+            previous => {
+                if let Some(previous) = previous {
+                    unsafe {
+                        previous.destroy();
                     }
                 }
 
-                SystemWidget::Button(x) => {
-                    if x.is_invalidated() {
-                        invalidated = true;
-                        self.draw(i as u32, c, fc);
+                damage = vec![make_rect(Point::new(0, 0), size)];
+                c.create_texture_target(None, size.w, size.h)
+                    .map_err(|e| PushrodError::texture(size, e))?
+            }
+        };
+
+        let damage = Self::merge_damage(damage);
+        let mut result = Ok(());
+
+        debug!(target: "pushrod::draw", "Composing scene: damaged areas={}", damage.len());
+
+        if !damage.is_empty() {
+            c.with_texture_canvas(&mut scene, |sc| {
+                for rect in damage.iter() {
+                    sc.set_clip_rect(*rect);
+                    sc.set_draw_color(Color::RGBA(255, 255, 255, 255));
+
+                    if result.is_ok() {
+                        result = sc.fill_rect(*rect).and_then(|_| self.compose_tree(WidgetId::default(), *rect, sc));
                     }
                 }
 
-                _unused => {
-                    panic!("[WidgetCache::draw_loop] I'm sent a widget that I can't draw yet! (needs to be implemented in 'draw_loop')");
-                }
-            }
+                sc.set_clip_rect(None);
+            })?;
         }
 
-        invalidated
-    }
+        // The scene is kept even if it could not be composed, so that it is not recreated, and
+        // the damaged areas are composed again on the next draw.
+        let copied = result.and_then(|_| c.copy(&scene, None, None));
 
-    /// Internal function that draws the object texture to the GPU.  Clears the invalidation flag
-    /// on the `Widget` once blitted.
-    fn draw(&mut self, widget_id: u32, c: &mut Canvas<Window>, fc: &mut FontCache) {
-        match &mut self.cache[widget_id as usize] {
-            SystemWidget::Base(ref mut widget) => {
-                let widget_origin = widget.get_origin().clone();
-                let widget_size = widget.get_size().clone();
+        self.scene = Some(scene);
+        copied.map_err(PushrodError::Render)?;
 
-                match widget.draw(c, fc) {
-                    Some(texture) => c
-                        .copy(texture, None, make_rect(widget_origin, widget_size))
-                        .unwrap(),
+        Ok(exposed || !damage.is_empty())
+    }
 
-                    None => panic!("[WidgetCache::draw] BASE: No texture presented."),
-                };
+    /// Indicates whether or not the next call to `draw_loop` would change the scene: any area is
+    /// damaged, any visible `Widget` is invalidated, or the window was exposed.
+    pub fn needs_redraw(&self) -> bool {
+        !self.cache.is_empty()
+            && (self.scene.is_none()
+                || self.exposed
+                || !self.damage.is_empty()
+                || self.subtree_needs_redraw(WidgetId::default()))
+    }
 
-                widget.set_invalidated(false);
+    /// Internal function that checks whether a `Widget`, or any of its children, is visible and
+    /// invalidated.
+    fn subtree_needs_redraw(&self, widget_id: WidgetId) -> bool {
+        match self.entry(widget_id) {
+            Some(entry) if entry.widget.as_widget().is_visible() => {
+                entry.widget.as_widget().is_invalidated()
+                    || entry.children.iter().any(|x| self.subtree_needs_redraw(*x))
             }
+            _ => false,
+        }
+    }
 
-            SystemWidget::Box(ref mut widget) => {
-                let widget_origin = widget.get_origin().clone();
-                let widget_size = widget.get_size().clone();
-
-                match widget.draw(c, fc) {
-                    Some(texture) => c
-                        .copy(texture, None, make_rect(widget_origin, widget_size))
-                        .unwrap(),
+    /// Internal function that redraws the texture of each visible, invalidated `Widget` in a
+    /// subtree, adding the area each one occupies to the `damage` list.  Clears the invalidation
+    /// flag on each `Widget` once redrawn.  `Widget`s that fail to draw are reported, and their
+    /// invalidation flag is cleared, so that they are not redrawn on every frame.
+    fn render_tree(&mut self, widget_id: WidgetId, damage: &mut Vec<Rect>, c: &mut Canvas<Window>, fc: &mut FontCache) {
+        let clip_rect = self.get_clip_rect(widget_id);
+        let children = match self.entry_mut(widget_id) {
+            Some(entry) if entry.widget.as_widget().is_visible() => {
+                let widget = entry.widget.as_widget_mut();
+
+                if widget.is_invalidated() {
+                    trace!(target: "pushrod::draw", "Drawing widget {}", widget_id);
+
+                    if let Err(e) = widget.draw(c, fc) {
+                        error!(target: "pushrod::draw", "Unable to draw widget {}: {}", widget_id, e);
+                    }
 
-                    None => panic!("[WidgetCache::draw] BOX: No texture presented."),
-                };
+                    widget.set_invalidated(false);
+                    damage.extend(clip_rect);
+                }
 
-                widget.set_invalidated(false);
+                entry.children.clone()
             }
+            _ => return,
+        };
 
-            SystemWidget::Text(ref mut widget) => {
-                let widget_origin = widget.get_origin().clone();
-                let widget_size = widget.get_size().clone();
+        for child_id in children {
+            self.render_tree(child_id, damage, c, fc);
+        }
+    }
 
-                match widget.draw(c, fc) {
-                    Some(texture) => c
-                        .copy(texture, None, make_rect(widget_origin, widget_size))
-                        .unwrap(),
+    /// Internal function that merges overlapping damaged areas, so that no area of the scene is
+    /// composed more than once.
+    fn merge_damage(mut damage: Vec<Rect>) -> Vec<Rect> {
+        let mut merged: Vec<Rect> = Vec::with_capacity(damage.len());
 
-                    None => panic!("[WidgetCache::draw] TEXT: No texture presented."),
-                };
-
-                widget.set_invalidated(false);
+        while let Some(mut rect) = damage.pop() {
+            // Merging two areas may cause the result to overlap an area that was already merged,
+            // so the merged list is searched again until no overlaps remain.
+            while let Some(position) = merged.iter().position(|x| x.has_intersection(rect)) {
+                rect = rect.union(merged.swap_remove(position));
             }
 
-            SystemWidget::Button(ref mut widget) => {
-                let widget_origin = widget.get_origin().clone();
-                let widget_size = widget.get_size().clone();
-
-                match widget.draw(c, fc) {
-                    Some(texture) => c
-                        .copy(texture, None, make_rect(widget_origin, widget_size))
-                        .unwrap(),
+            merged.push(rect);
+        }
 
-                    None => panic!("[WidgetCache::draw] BUTTON: No texture presented."),
-                };
+        merged
+    }
 
-                widget.set_invalidated(false);
+    /// Internal function that composes a `Widget` and its children into the area of the scene
+    /// specified, in tree order.  Children are skipped if their parent does not intersect the
+    /// area, as they are clipped to the bounds of their parent.
+    fn compose_tree(&mut self, widget_id: WidgetId, area: Rect, c: &mut Canvas<Window>) -> Result<(), String> {
+        let visible_area = match self.entry(widget_id) {
+            Some(entry) if entry.widget.as_widget().is_visible() => {
+                self.get_clip_rect(widget_id).and_then(|x| x.intersection(area))
             }
+            _ => return Ok(()),
+        };
+
+        if let Some(visible_area) = visible_area {
+            self.compose(widget_id, visible_area, c)?;
+
+            let children = self
+                .entry(widget_id)
+                .map(|x| x.children.clone())
+                .unwrap_or_default();
 
-            _default => {
-                panic!("[WidgetCache::draw] I'm sent a widget that I can't draw yet! (needs to be implemented in 'draw')");
+            for child_id in children {
+                self.compose_tree(child_id, area, c)?;
             }
         }
+
+        Ok(())
     }
 
-    // Returns the top-most `Widget` ID given `x` and `y` coordinates.  Returns 0 if no widget
-    // was found (which indicates the top-level `Widget` ID of the window).
-    //
-    // BUG: Does not currently handle widget Z coordinates, as no Z coordinates currently exist.
-    // "Widget on Top" is not yet implemented, so get widget ID will return the last object that
-    // exists in the coordinates given.  Overlapped objects will return the top-most object based
-    // on the insertion order in the cache.
-    fn get_widget_id(&self, x: i32, y: i32) -> u32 {
-        let cache_size = self.cache.len();
-
-        let mut widget_id = 0;
-
-        for i in 0..cache_size {
-            let mut start_x = 0;
-            let mut end_x = 0;
-            let mut start_y = 0;
-            let mut end_y = 0;
-
-            match &self.cache[i] {
-                SystemWidget::Base(x) => {
-                    start_x = x.get_origin().x;
-                    start_y = x.get_origin().y;
-                    end_x = start_x + x.get_size().w as i32;
-                    end_y = start_y + x.get_size().h as i32;
-                }
+    /// Internal function that copies the cached texture of a `Widget` to the scene, clipped to
+    /// the visible area specified, along with its disabled overlay and focus ring.
+    fn compose(&mut self, widget_id: WidgetId, visible_area: Rect, c: &mut Canvas<Window>) -> Result<(), String> {
+        let widget_rect = match self.get_absolute_rect(widget_id) {
+            Some(widget_rect) => widget_rect,
+            None => return Ok(()),
+        };
+        let enabled = self.is_enabled(widget_id);
+        let focused = self.focused_widget_id == Some(widget_id);
+        let widget = match self.entry_mut(widget_id) {
+            Some(entry) => entry.widget.as_widget_mut(),
+            None => return Ok(()),
+        };
+
+        c.set_clip_rect(visible_area);
+
+        // Widgets that do not draw anything present no texture, so there is nothing to blit.
+        if let Some(texture) = widget.get_texture().get_optional_ref() {
+            c.copy(texture, None, widget_rect)?;
+        }
 
-                SystemWidget::Box(x) => {
-                    start_x = x.get_origin().x;
-                    start_y = x.get_origin().y;
-                    end_x = start_x + x.get_size().w as i32;
-                    end_y = start_y + x.get_size().h as i32;
-                }
+        if !enabled {
+            let blend_mode = c.blend_mode();
 
-                SystemWidget::Text(x) => {
-                    start_x = x.get_origin().x;
-                    start_y = x.get_origin().y;
-                    end_x = start_x + x.get_size().w as i32;
-                    end_y = start_y + x.get_size().h as i32;
-                }
+            c.set_blend_mode(BlendMode::Blend);
+            c.set_draw_color(DISABLED_OVERLAY_COLOR);
+            c.fill_rect(widget_rect)?;
+            c.set_blend_mode(blend_mode);
+        }
 
-                SystemWidget::Button(x) => {
-                    start_x = x.get_origin().x;
-                    start_y = x.get_origin().y;
-                    end_x = start_x + x.get_size().w as i32;
-                    end_y = start_y + x.get_size().h as i32;
-                }
+        if focused {
+            c.set_draw_color(FOCUS_RING_COLOR);
 
-                _default => {
-                    panic!("[WidgetCache::get_widget_id] Unable to get widget by ID {}, cannot handle yet.", i);
-                    // Do nothing.
-                }
+            for i in 0..FOCUS_RING_WIDTH.min(widget_rect.width() / 2).min(widget_rect.height() / 2) {
+                c.draw_rect(Rect::new(
+                    widget_rect.x() + i as i32,
+                    widget_rect.y() + i as i32,
+                    widget_rect.width() - i * 2,
+                    widget_rect.height() - i * 2,
+                ))?;
             }
+        }
+
+        Ok(())
+    }
+
+    // Returns the top-most `Widget` ID given `x` and `y` coordinates.  Returns the default ID if no
+    // widget was found (which indicates the top-level `Widget` ID of the window).
+    //
+    // The tree is searched from the top-level `Widget` down, so the deepest `Widget` containing
+    // the point is returned.  Siblings are searched from the highest Z index to the lowest, in
+    // the reverse of drawing order.  Hidden `Widget`s, and points outside of the bounds of a
+    // `Widget`'s parents, are ignored.
+    fn get_widget_id(&self, x: i32, y: i32) -> WidgetId {
+        self.find_widget_id(WidgetId::default(), Point::new(x, y))
+            .unwrap_or_default()
+    }
 
-            if x >= start_x && x <= end_x && y >= start_y && y <= end_y {
-                widget_id = i as u32;
+    /// Internal function that returns the deepest `Widget` in the subtree of `widget_id`
+    /// containing the `point`, or `None` if the `Widget` does not contain the `point`.
+    fn find_widget_id(&self, widget_id: WidgetId, point: Point) -> Option<WidgetId> {
+        let entry = self.entry(widget_id)?;
+
+        if !entry.widget.as_widget().is_visible() || !self.get_absolute_rect(widget_id)?.contains_point((point.x, point.y)) {
+            return None;
+        }
+
+        // Children drawn last (highest Z index) are drawn on top, so they are checked first.
+        for child_id in entry.children.iter().rev() {
+            if let Some(found_id) = self.find_widget_id(*child_id, point) {
+                return Some(found_id);
             }
         }
 
-        widget_id
-    }
-
-    // fn draw(&mut self, _widget_id: i32, _c: &mut Canvas<Window>) {
-    //     // let parents_of_widget = self.get_children_of(widget_id);
-    //     //
-    //     // if parents_of_widget.is_empty() {
-    //     //     return;
-    //     // }
-    //     //
-    //     // for paint_id in &parents_of_widget {
-    //     //     let paint_widget = &mut self.cache[*paint_id as usize];
-    //     //     let is_hidden = paint_widget.widget.borrow_mut().get_config().is_hidden();
-    //     //     let is_enabled = paint_widget.widget.borrow_mut().get_config().is_enabled();
-    //     //     let widget_x = paint_widget.widget.borrow_mut().get_config().to_x(0);
-    //     //     let widget_y = paint_widget.widget.borrow_mut().get_config().to_y(0);
-    //     //     let widget_w = paint_widget
-    //     //         .widget
-    //     //         .borrow_mut()
-    //     //         .get_config()
-    //     //         .get_size(CONFIG_SIZE)[0];
-    //     //     let widget_h = paint_widget
-    //     //         .widget
-    //     //         .borrow_mut()
-    //     //         .get_config()
-    //     //         .get_size(CONFIG_SIZE)[1];
-    //     //
-    //     //     if !is_hidden {
-    //     //         match paint_widget
-    //     //             .widget
-    //     //             .borrow_mut()
-    //     //             .draw(c, &mut self.texture_cache)
-    //     //         {
-    //     //             Some(texture) => {
-    //     //                 c.copy(
-    //     //                     texture,
-    //     //                     None,
-    //     //                     Rect::new(widget_x, widget_y, widget_w, widget_h),
-    //     //                 )
-    //     //                     .unwrap();
-    //     //             }
-    //     //             None => eprintln!("No texture presented: ID={}", paint_id),
-    //     //         };
-    //     //
-    //     //         paint_widget.widget.borrow_mut().set_invalidated(false);
-    //     //     }
-    //     //
-    //     //     if *paint_id != widget_id {
-    //     //         self.draw(*paint_id, c);
-    //     //     }
-    //     //
-    //     //     if !is_enabled {
-    //     //         c.set_draw_color(Color::RGBA(0, 0, 0, 128));
-    //     //         c.draw_rect(Rect::new(widget_x, widget_y, widget_w, widget_h))
-    //     //             .unwrap();
-    //     //     }
-    //     // }
-    // }
+        Some(widget_id)
+    }
 }
 
 impl Default for WidgetCache {
@@ -486,3 +1181,564 @@ impl Default for WidgetCache {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_widget::BaseWidget;
+    use crate::button_widget::ButtonWidget;
+    use crate::impl_widget_base;
+    use crate::text_widget::TextJustify;
+    use crate::texture::TextureStore;
+    use sdl2::mouse::{MouseButton, MouseState};
+    use sdl2::ttf::FontStyle;
+    use std::any::Any;
+
+    /// A focusable `Widget` that draws nothing, and records every event it is sent.
+    struct RecordingWidget {
+        id: WidgetId,
+        origin: Point,
+        size: Size,
+        invalidated: bool,
+        visible: bool,
+        enabled: bool,
+        texture: TextureStore,
+        base_widget: BaseWidget,
+        received: Vec<PushrodEvent>,
+    }
+
+    impl Widget for RecordingWidget {
+        fn handle_event(&mut self, event: PushrodEvent, _context: &mut EventContext) {
+            self.received.push(event);
+        }
+
+        fn draw(&mut self, _c: &mut Canvas<Window>, _fc: &mut FontCache) -> Result<Option<&Texture>, PushrodError> {
+            Ok(None)
+        }
+
+        fn is_focusable(&self) -> bool {
+            true
+        }
+
+        impl_widget_base!();
+    }
+
+    /// Creates a `RecordingWidget` at the origin and of the size specified.
+    fn recording(x: i32, y: i32, w: u32, h: u32) -> SystemWidget {
+        SystemWidget::Custom(Box::new(RecordingWidget {
+            id: WidgetId::default(),
+            origin: Point::new(x, y),
+            size: Size::new(w, h),
+            invalidated: true,
+            visible: true,
+            enabled: true,
+            texture: TextureStore::default(),
+            base_widget: BaseWidget::new(Point::new(0, 0), Size::new(w, h)),
+            received: Vec::new(),
+        }))
+    }
+
+    /// Returns the events received by the `RecordingWidget` with the ID specified.
+    fn received(cache: &WidgetCache, widget_id: WidgetId) -> &[PushrodEvent] {
+        cache
+            .get(widget_id)
+            .and_then(|x| x.as_widget().as_any().downcast_ref::<RecordingWidget>())
+            .map(|x| x.received.as_slice())
+            .unwrap()
+    }
+
+    /// Creates a `WidgetCache` containing three focusable siblings side by side, at `0,0`,
+    /// `50,0` and `100,0`.  Returns the cache, along with the IDs of the siblings.
+    fn focus_cache() -> (WidgetCache, [WidgetId; 3]) {
+        let mut cache = WidgetCache::new();
+
+        cache.add(base(0, 0, 200, 200));
+
+        let a = cache.add(recording(0, 0, 40, 40));
+        let b = cache.add(recording(50, 0, 40, 40));
+        let c = cache.add(recording(100, 0, 40, 40));
+
+        (cache, [a, b, c])
+    }
+
+    /// Creates a `KeyDown` event for the key and modifiers specified.
+    fn key_down(keycode: Keycode, keymod: Mod) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 1,
+            keycode: Some(keycode),
+            scancode: None,
+            keymod,
+            repeat: false,
+        }
+    }
+
+    /// Creates a `BaseWidget` at the origin and of the size specified.
+    fn base(x: i32, y: i32, w: u32, h: u32) -> SystemWidget {
+        SystemWidget::Base(Box::new(BaseWidget::new(Point::new(x, y), Size::new(w, h))))
+    }
+
+    /// Creates a `ButtonWidget` at the origin and of the size specified.  The font is only loaded
+    /// when the button is drawn, so it does not need to exist.
+    fn button(x: i32, y: i32, w: u32, h: u32) -> SystemWidget {
+        SystemWidget::Button(Box::new(ButtonWidget::new(
+            Point::new(x, y),
+            Size::new(w, h),
+            String::from("assets/OpenSans-Regular.ttf"),
+            FontStyle::NORMAL,
+            14,
+            Color::BLACK,
+            TextJustify::Center,
+            String::from("Button"),
+            1,
+        )))
+    }
+
+    /// Creates a `MouseMotion` event with the pointer at `x,y`.
+    fn mouse_motion(x: i32, y: i32) -> Event {
+        Event::MouseMotion {
+            timestamp: 0,
+            window_id: 1,
+            which: 0,
+            mousestate: MouseState::from_sdl_state(0),
+            x,
+            y,
+            xrel: 0,
+            yrel: 0,
+        }
+    }
+
+    /// Creates a left `MouseButtonUp` event at `x,y`.
+    fn mouse_up(x: i32, y: i32) -> Event {
+        Event::MouseButtonUp {
+            timestamp: 0,
+            window_id: 1,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x,
+            y,
+        }
+    }
+
+    /// Creates a left `MouseButtonDown` event at `x,y`.
+    fn mouse_down(x: i32, y: i32) -> Event {
+        Event::MouseButtonDown {
+            timestamp: 0,
+            window_id: 1,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x,
+            y,
+        }
+    }
+
+    /// Creates a `WidgetCache` containing a 200x200 top-level `Widget`, a 100x100 panel at
+    /// `10,20`, and a 50x50 child of the panel at `5,5`.  Returns the cache, along with the IDs
+    /// of the panel and its child.
+    fn nested_cache() -> (WidgetCache, WidgetId, WidgetId) {
+        let mut cache = WidgetCache::new();
+
+        cache.add(base(0, 0, 200, 200));
+
+        let panel_id = cache.add(base(10, 20, 100, 100));
+        let child_id = cache.add_child(panel_id, base(5, 5, 50, 50)).unwrap();
+
+        (cache, panel_id, child_id)
+    }
+
+    #[test]
+    fn test_add_child_sets_parent_and_id() {
+        let (cache, panel_id, child_id) = nested_cache();
+
+        assert_eq!(cache.get_parent(panel_id), Some(WidgetId::default()));
+        assert_eq!(cache.get_parent(child_id), Some(panel_id));
+        assert_eq!(cache.get_parent(WidgetId::default()), None);
+        assert_eq!(cache.get_children_of(panel_id), vec![child_id]);
+        assert_eq!(cache.get(child_id).unwrap().as_widget().get_id(), child_id);
+    }
+
+    #[test]
+    fn test_add_child_invalid_parent() {
+        let (mut cache, _, _) = nested_cache();
+        let missing_id = WidgetId::new(99, 0);
+
+        assert!(matches!(
+            cache.add_child(missing_id, base(0, 0, 10, 10)),
+            Err(PushrodError::InvalidWidget(x)) if x == missing_id
+        ));
+        assert_eq!(cache.get_widget_ids().count(), 3);
+    }
+
+    #[test]
+    fn test_absolute_origin_nested() {
+        let (cache, panel_id, child_id) = nested_cache();
+
+        assert_eq!(cache.get_absolute_origin(WidgetId::default()), Some(Point::new(0, 0)));
+        assert_eq!(cache.get_absolute_origin(panel_id), Some(Point::new(10, 20)));
+        assert_eq!(cache.get_absolute_origin(child_id), Some(Point::new(15, 25)));
+        assert_eq!(cache.get_absolute_rect(child_id), Some(Rect::new(15, 25, 50, 50)));
+        assert_eq!(cache.get_absolute_origin(WidgetId::new(99, 0)), None);
+    }
+
+    #[test]
+    fn test_absolute_origin_follows_parent() {
+        let (mut cache, panel_id, child_id) = nested_cache();
+
+        cache.set_origin(panel_id, Point::new(30, 40)).unwrap();
+
+        assert_eq!(cache.get_absolute_origin(child_id), Some(Point::new(35, 45)));
+    }
+
+    #[test]
+    fn test_clip_rect() {
+        let (mut cache, panel_id, child_id) = nested_cache();
+
+        assert_eq!(cache.get_clip_rect(child_id), Some(Rect::new(15, 25, 50, 50)));
+
+        // A child extending past the bottom right corner of its parent is clipped to it.
+        cache.set_origin(child_id, Point::new(80, 90)).unwrap();
+
+        assert_eq!(cache.get_clip_rect(child_id), Some(Rect::new(90, 110, 20, 10)));
+
+        // A child entirely outside of its parent cannot draw at all.
+        cache.set_origin(child_id, Point::new(150, 0)).unwrap();
+
+        assert_eq!(cache.get_clip_rect(child_id), None);
+        assert_eq!(cache.get_clip_rect(panel_id), Some(Rect::new(10, 20, 100, 100)));
+    }
+
+    #[test]
+    fn test_find_widget_id() {
+        let (mut cache, panel_id, child_id) = nested_cache();
+
+        assert_eq!(cache.get_widget_id(20, 30), child_id);
+        assert_eq!(cache.get_widget_id(100, 100), panel_id);
+        assert_eq!(cache.get_widget_id(150, 150), WidgetId::default());
+        assert_eq!(cache.get_widget_id(500, 500), WidgetId::default());
+
+        // Hidden `Widget`s are skipped, so the point falls through to the panel.
+        cache.set_visible(child_id, false).unwrap();
+
+        assert_eq!(cache.get_widget_id(20, 30), panel_id);
+
+        // Hiding the panel hides its entire subtree.
+        cache.set_visible(child_id, true).unwrap();
+        cache.set_visible(panel_id, false).unwrap();
+
+        assert_eq!(cache.get_widget_id(20, 30), WidgetId::default());
+    }
+
+    #[test]
+    fn test_find_widget_id_outside_parent() {
+        let (mut cache, panel_id, child_id) = nested_cache();
+
+        cache.set_origin(child_id, Point::new(80, 80)).unwrap();
+
+        // The part of the child inside the panel is hit, but the part outside of it is not.
+        assert_eq!(cache.get_widget_id(100, 110), child_id);
+        assert_eq!(cache.get_widget_id(130, 130), WidgetId::default());
+        assert_eq!(cache.get_widget_id(50, 50), panel_id);
+    }
+
+    /// Creates a `WidgetCache` containing three overlapping 50x50 siblings at `10,10`, `20,20` and
+    /// `30,30`, added in that order.  Returns the cache, along with the IDs of the siblings.
+    fn sibling_cache() -> (WidgetCache, [WidgetId; 3]) {
+        let mut cache = WidgetCache::new();
+
+        cache.add(base(0, 0, 200, 200));
+
+        let a = cache.add(base(10, 10, 50, 50));
+        let b = cache.add(base(20, 20, 50, 50));
+        let c = cache.add(base(30, 30, 50, 50));
+
+        (cache, [a, b, c])
+    }
+
+    #[test]
+    fn test_equal_z_keeps_insertion_order() {
+        let (mut cache, [a, b, c]) = sibling_cache();
+
+        assert_eq!(cache.get_children_of(WidgetId::default()), vec![a, b, c]);
+
+        // Setting the same Z index again does not reorder the siblings.
+        cache.set_z(b, 0).unwrap();
+
+        assert_eq!(cache.get_children_of(WidgetId::default()), vec![a, b, c]);
+
+        cache.set_z(a, 1).unwrap();
+
+        assert_eq!(cache.get_children_of(WidgetId::default()), vec![b, c, a]);
+
+        // Siblings moved to the same Z index keep their existing order.
+        cache.set_z(b, 1).unwrap();
+
+        assert_eq!(cache.get_children_of(WidgetId::default()), vec![c, b, a]);
+        assert_eq!(cache.get_z(b), Some(1));
+    }
+
+    #[test]
+    fn test_raise_and_lower_order() {
+        let (mut cache, [a, b, c]) = sibling_cache();
+
+        // The point is inside all three siblings, so the top-most sibling is hit.
+        assert_eq!(cache.get_widget_id(40, 40), c);
+
+        cache.raise_to_top(a).unwrap();
+
+        assert_eq!(cache.get_children_of(WidgetId::default()), vec![b, c, a]);
+        assert_eq!(cache.get_widget_id(40, 40), a);
+
+        cache.lower_to_bottom(a).unwrap();
+
+        assert_eq!(cache.get_children_of(WidgetId::default()), vec![a, b, c]);
+        assert_eq!(cache.get_widget_id(40, 40), c);
+
+        cache.lower_to_bottom(c).unwrap();
+
+        assert_eq!(cache.get_children_of(WidgetId::default()), vec![c, a, b]);
+        assert_eq!(cache.get_widget_id(40, 40), b);
+
+        // A point only inside the lowered sibling still hits it.
+        assert_eq!(cache.get_widget_id(75, 75), c);
+    }
+
+    #[test]
+    fn test_raise_and_lower_invalid_widget() {
+        let (mut cache, _) = sibling_cache();
+        let missing_id = WidgetId::new(99, 0);
+
+        assert!(cache.raise_to_top(WidgetId::default()).is_ok());
+        assert!(cache.lower_to_bottom(WidgetId::default()).is_ok());
+        assert!(matches!(cache.raise_to_top(missing_id), Err(PushrodError::InvalidWidget(_))));
+        assert!(matches!(cache.lower_to_bottom(missing_id), Err(PushrodError::InvalidWidget(_))));
+        assert!(matches!(cache.set_z(missing_id, 1), Err(PushrodError::InvalidWidget(_))));
+    }
+
+    #[test]
+    fn test_remove_stale_id() {
+        let (mut cache, [a, _, _]) = sibling_cache();
+
+        assert!(cache.remove(a).is_ok());
+        assert!(!cache.contains(a));
+
+        // The slot of the removed `Widget` is recycled, but its ID does not resolve to the new
+        // `Widget`.
+        let d = cache.add(base(0, 0, 10, 10));
+
+        assert_eq!(d.get_index(), a.get_index());
+        assert_ne!(d, a);
+        assert!(cache.contains(d));
+        assert!(cache.get(a).is_none());
+        assert!(cache.get_parent(a).is_none());
+        assert!(matches!(cache.remove(a), Err(PushrodError::InvalidWidget(x)) if x == a));
+        assert!(matches!(cache.set_origin(a, Point::new(1, 1)), Err(PushrodError::InvalidWidget(_))));
+        assert_eq!(*cache.get(d).unwrap().as_widget().get_origin(), Point::new(0, 0));
+    }
+
+    #[test]
+    fn test_remove_subtree() {
+        let (mut cache, panel_id, child_id) = nested_cache();
+        let removed = cache.remove(panel_id).unwrap();
+
+        assert_eq!(removed.as_widget().get_id(), panel_id);
+        assert!(!cache.contains(panel_id));
+        assert!(!cache.contains(child_id));
+        assert!(cache.get_children_of(WidgetId::default()).is_empty());
+        assert_eq!(cache.get_widget_ids().collect::<Vec<_>>(), vec![WidgetId::default()]);
+        assert_eq!(cache.get_widget_id(20, 30), WidgetId::default());
+    }
+
+    #[test]
+    fn test_remove_top_level_widget() {
+        let (mut cache, _, _) = nested_cache();
+
+        assert!(matches!(
+            cache.remove(WidgetId::default()),
+            Err(PushrodError::InvalidWidget(x)) if x == WidgetId::default()
+        ));
+        assert!(cache.contains(WidgetId::default()));
+    }
+
+    #[test]
+    fn test_remove_resets_current_and_focused_widget() {
+        let mut cache = WidgetCache::new();
+
+        cache.add(base(0, 0, 200, 200));
+
+        let panel_id = cache.add(base(10, 10, 100, 100));
+        let button_id = cache.add_child(panel_id, button(10, 10, 50, 50)).unwrap();
+
+        cache.handle_event(mouse_motion(30, 30));
+        cache.handle_event(mouse_down(30, 30));
+
+        assert_eq!(cache.get_current_widget(), button_id);
+        assert_eq!(cache.get_focused_widget(), Some(button_id));
+
+        cache.remove(panel_id).unwrap();
+
+        assert_eq!(cache.get_current_widget(), WidgetId::default());
+        assert_eq!(cache.get_focused_widget(), None);
+    }
+
+    #[test]
+    fn test_disabling_parent_cancels_press() {
+        let mut cache = WidgetCache::new();
+
+        cache.add(base(0, 0, 200, 200));
+
+        let panel_id = cache.add(base(10, 10, 100, 100));
+        let button_id = cache.add_child(panel_id, button(10, 10, 50, 50)).unwrap();
+        let is_pressed = |cache: &WidgetCache| {
+            cache
+                .get(button_id)
+                .and_then(|x| x.as_widget().as_any().downcast_ref::<ButtonWidget>())
+                .map(|x| x.is_pressed())
+        };
+
+        cache.handle_event(mouse_motion(30, 30));
+        cache.handle_event(mouse_down(30, 30));
+
+        assert_eq!(is_pressed(&cache), Some(true));
+
+        cache.set_enabled(panel_id, false).unwrap();
+
+        assert_eq!(is_pressed(&cache), Some(false));
+        assert!(!cache.is_enabled(button_id));
+
+        // The release after the panel is enabled again does not complete the cancelled press.
+        cache.set_enabled(panel_id, true).unwrap();
+
+        let events = cache.handle_event(mouse_up(30, 30));
+
+        assert!(!events.iter().any(|x| matches!(x, PushrodEvent::Clicked(..))));
+    }
+
+    #[test]
+    fn test_tab_wraps_around() {
+        let (mut cache, [a, b, c]) = focus_cache();
+        let tab = || key_down(Keycode::Tab, Mod::NOMOD);
+        let shift_tab = || key_down(Keycode::Tab, Mod::LSHIFTMOD);
+
+        assert_eq!(cache.get_focused_widget(), None);
+
+        for expected in [a, b, c, a] {
+            cache.handle_event(tab());
+
+            assert_eq!(cache.get_focused_widget(), Some(expected));
+        }
+
+        for expected in [c, b, a, c] {
+            cache.handle_event(shift_tab());
+
+            assert_eq!(cache.get_focused_widget(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_tab_order() {
+        let (mut cache, [a, b, c]) = focus_cache();
+
+        cache.set_tab_order(vec![c, a]);
+
+        assert_eq!(cache.get_tab_order(), vec![c, a]);
+
+        for expected in [c, a, c] {
+            cache.handle_event(key_down(Keycode::Tab, Mod::NOMOD));
+
+            assert_eq!(cache.get_focused_widget(), Some(expected));
+        }
+
+        assert!(!received(&cache, b).iter().any(|x| matches!(x, PushrodEvent::FocusGained(_))));
+    }
+
+    #[test]
+    fn test_tab_skips_hidden_and_disabled() {
+        let (mut cache, [a, b, c]) = focus_cache();
+
+        cache.set_focused_widget(Some(a));
+        cache.set_visible(b, false).unwrap();
+        cache.handle_event(key_down(Keycode::Tab, Mod::NOMOD));
+
+        assert_eq!(cache.get_focused_widget(), Some(c));
+
+        cache.set_enabled(c, false).unwrap();
+        cache.handle_event(key_down(Keycode::Tab, Mod::NOMOD));
+
+        assert_eq!(cache.get_focused_widget(), Some(a));
+
+        // No other `Widget` can be focused, so focus stays where it is.
+        let events = cache.handle_event(key_down(Keycode::Tab, Mod::NOMOD));
+
+        assert_eq!(cache.get_focused_widget(), Some(a));
+        assert!(events.is_empty());
+
+        // Hidden or disabled `Widget`s cannot be focused directly either.
+        assert!(cache.set_focused_widget(Some(b)).is_empty());
+        assert_eq!(cache.get_focused_widget(), Some(a));
+    }
+
+    #[test]
+    fn test_click_to_focus() {
+        let (mut cache, [a, b, _]) = focus_cache();
+
+        cache.set_focused_widget(Some(a));
+
+        let events = cache.handle_event(mouse_down(60, 10));
+
+        assert_eq!(cache.get_focused_widget(), Some(b));
+        assert!(events.iter().any(|x| matches!(x, PushrodEvent::FocusLost(x) if *x == a)));
+        assert!(events.iter().any(|x| matches!(x, PushrodEvent::FocusGained(x) if *x == b)));
+        assert!(received(&cache, a).iter().any(|x| matches!(x, PushrodEvent::FocusLost(_))));
+        assert!(received(&cache, b).iter().any(|x| matches!(x, PushrodEvent::FocusGained(_))));
+
+        // Clicking a `Widget` that cannot be focused leaves focus where it is.
+        cache.handle_event(mouse_down(150, 150));
+
+        assert_eq!(cache.get_focused_widget(), Some(b));
+    }
+
+    #[test]
+    fn test_key_events_sent_to_focused_widget() {
+        let (mut cache, [a, b, _]) = focus_cache();
+
+        // Keyboard input is not delivered to anything until a `Widget` has focus.
+        cache.handle_event(key_down(Keycode::A, Mod::NOMOD));
+
+        assert!(received(&cache, a).is_empty());
+        assert!(received(&cache, b).is_empty());
+
+        cache.set_focused_widget(Some(b));
+
+        // The pointer is over `a`, but keyboard input goes to the focused `Widget`.
+        cache.handle_event(mouse_motion(10, 10));
+        cache.handle_event(key_down(Keycode::A, Mod::NOMOD));
+        cache.handle_event(Event::TextInput {
+            timestamp: 0,
+            window_id: 1,
+            text: String::from("a"),
+        });
+
+        let received_b = received(&cache, b);
+
+        assert!(received_b.iter().any(|x| matches!(
+            x,
+            PushrodEvent::KeyDown { widget_id, keycode: Some(Keycode::A), .. } if *widget_id == b
+        )));
+        assert!(received_b.iter().any(|x| matches!(
+            x,
+            PushrodEvent::TextInput(widget_id, text) if *widget_id == b && text == "a"
+        )));
+        assert!(!received(&cache, a)
+            .iter()
+            .any(|x| matches!(x, PushrodEvent::KeyDown { .. } | PushrodEvent::TextInput(..))));
+
+        // `Tab` moves focus, and is not delivered as a key press.
+        cache.handle_event(key_down(Keycode::Tab, Mod::NOMOD));
+
+        assert!(!received(&cache, b)
+            .iter()
+            .any(|x| matches!(x, PushrodEvent::KeyDown { keycode: Some(Keycode::Tab), .. })));
+    }
+}
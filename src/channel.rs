@@ -0,0 +1,136 @@
+// Engine Channel
+// Pushrod
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # EngineSender
+//!
+//! A thread-safe channel for posting work into a running `Engine` from other threads.
+//!
+//! The `Engine` owns all of the UI state, and runs on a single thread.  Background workers, such
+//! as network requests or file loading, use an `EngineSender` to notify the UI when their work is
+//! complete.  An `EngineSender` is retrieved from the `Engine` using `get_sender`, and can be
+//! cloned and sent to any number of threads.
+//!
+//! Two kinds of messages can be posted:
+//!
+//! - Events, containing any `Send` payload, which are delivered to the `EventHandler` as
+//!   `Custom` `PushrodEvent`s.
+//! - Closures, which are run on the UI thread with a `HandlerContext`, so that they can change
+//!   the `Widget`s in the UI directly.
+//!
+//! Messages are processed in the order in which they were posted, during the next iteration of
+//! the run loop.  Posting a message wakes the run loop, so messages are processed promptly even
+//! if the `Engine` is idle.
+//!
+//! ```rust,no_run
+//! # use pushrod::engine::Engine;
+//! # use pushrod::geometry::size;
+//! # use pushrod::text_widget::TextWidget;
+//! # use pushrod::widget::WidgetId;
+//! # let engine = Engine::new(size(800, 600), 60);
+//! # let label_id = WidgetId::default();
+//! let sender = engine.get_sender();
+//!
+//! std::thread::spawn(move || {
+//!     let result = String::from("Loaded");
+//!
+//!     sender
+//!         .post_call(move |context| {
+//!             if let Some(label) = context.get_widget_as::<TextWidget>(label_id) {
+//!                 label.set_text(result);
+//!             }
+//!         })
+//!         .unwrap();
+//! });
+//! ```
+
+use crate::error::PushrodError;
+use crate::event::{CustomEvent, HandlerContext};
+use sdl2::event::{Event, EventSender};
+use std::any::Any;
+use std::ptr;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+/// A closure posted to the `Engine`, run on the UI thread.
+pub(crate) type EngineCall = Box<dyn FnOnce(&mut HandlerContext) + Send>;
+
+/// A message posted to the `Engine` through an `EngineSender`.
+pub(crate) enum EngineMessage {
+    /// An application event, delivered to the `EventHandler` as a `Custom` event.
+    Event(CustomEvent),
+
+    /// A closure to run on the UI thread.
+    Call(EngineCall),
+}
+
+/// Wakes the run loop of an `Engine` when a message is posted.  The `EventSender` is only
+/// available once `SDL2` has been initialized by the run loop, so it is shared between the
+/// `Engine` and all of its `EngineSender`s.
+pub(crate) type EngineWaker = Arc<Mutex<Option<EventSender>>>;
+
+/// A cloneable, thread-safe handle used to post events and closures to an `Engine`.
+#[derive(Clone)]
+pub struct EngineSender {
+    sender: Sender<EngineMessage>,
+    waker: EngineWaker,
+}
+
+impl EngineSender {
+    /// Creates a new `EngineSender` that posts to the channel and wakes the `Engine` specified.
+    pub(crate) fn new(sender: Sender<EngineMessage>, waker: EngineWaker) -> Self {
+        Self { sender, waker }
+    }
+
+    /// Posts an application event to the `Engine`.  The event is delivered to the
+    /// `EventHandler` as a `Custom` event from the top-level `Widget`.  Returns an
+    /// `EngineClosed` error if the `Engine` no longer exists.
+    pub fn post_event<T: Any + Send + Sync>(&self, payload: T) -> Result<(), PushrodError> {
+        self.post(EngineMessage::Event(CustomEvent::new(payload)))
+    }
+
+    /// Posts a closure to the `Engine`, which is run on the UI thread with a `HandlerContext`.
+    /// Returns an `EngineClosed` error if the `Engine` no longer exists.
+    pub fn post_call<F: FnOnce(&mut HandlerContext) + Send + 'static>(&self, call: F) -> Result<(), PushrodError> {
+        self.post(EngineMessage::Call(Box::new(call)))
+    }
+
+    /// Internal function that posts a message, and wakes the run loop.
+    fn post(&self, message: EngineMessage) -> Result<(), PushrodError> {
+        self.sender
+            .send(message)
+            .map_err(|_| PushrodError::EngineClosed)?;
+
+        // The run loop may not have started yet, in which case it processes the message as soon
+        // as it does.
+        if let Some(waker) = self.waker.lock().unwrap().as_ref() {
+            waker.push_event(wake_event()).map_err(PushrodError::Sdl)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates the `SDL2` user event used to wake the run loop.  The event carries no data, and is
+/// ignored by the `WidgetCache`.
+fn wake_event() -> Event {
+    Event::User {
+        timestamp: 0,
+        window_id: 0,
+        type_: sdl2::sys::SDL_EventType::SDL_USEREVENT as u32,
+        code: 0,
+        data1: ptr::null_mut(),
+        data2: ptr::null_mut(),
+    }
+}
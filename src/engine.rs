@@ -26,19 +26,39 @@
 //! If an `event_handler` has been attached to the `Engine`, the event handler will be called,
 //! and passed in the resulting `PushrodEvent` with the message to process.  This function is where
 //! you would handle an event generated from a `Widget` that was interacted with, such as a
-//! mouse click, keyboard press, and so on.
+//! mouse click, keyboard press, and so on.  The event handler is given a `HandlerContext`, through
+//! which it can change the `Widget`s in the UI, or shut down the `Engine`.
+//!
+//! Other threads can post events and closures to the `Engine` using an `EngineSender`, retrieved
+//! using `get_sender`.  These are processed on the UI thread during the next iteration of the run
+//! loop.
+//!
+//! When the window is resized, the `Engine` updates its size, and resizes the top-level `Widget`
+//! to fill the window.  A `WindowResized` event is then sent to the top-level `Widget` and the
+//! event handler, which can rearrange the `Widget`s to fit the new size.  Windows must be created
+//! as resizable for `SDL2` to allow them to be resized.
+//!
+//! Timers are scheduled using `add_timer` and `add_repeating_timer`, and fire during the run loop,
+//! sending a `Timer` event to a `Widget`, or to the event handler.
 
+use crate::application::{run_windows, ApplicationWindow};
 use crate::base_widget::BaseWidget;
 use crate::cache::WidgetCache;
-use crate::event::EventHandler;
+use crate::channel::{EngineMessage, EngineSender, EngineWaker};
+use crate::error::PushrodError;
+use crate::event::{EventHandler, HandlerContext, PushrodEvent};
 use crate::geometry::{Point, Size};
-use crate::widget::{SystemWidget, Widget};
-use sdl2::event::Event;
+use crate::recorder::{EventPlayer, EventRecorder};
+use crate::timer::{TimerId, TimerQueue};
+use crate::widget::{SystemWidget, Widget, WidgetId};
+use log::{debug, warn};
+use sdl2::event::{Event, WindowEvent};
 use sdl2::pixels::Color;
+use sdl2::render::Canvas;
 use sdl2::video::Window;
-use sdl2::Sdl;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sdl2::{EventPump, Sdl};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
 use crate::font::FontCache;
 
 /// Engine structure.  Contains the desired display frame rate, size of the UI window, a
@@ -51,6 +71,13 @@ pub struct Engine {
     widget_cache: WidgetCache,
     font_cache: FontCache,
     event_handler: Option<Box<dyn EventHandler>>,
+    event_recorder: Option<EventRecorder>,
+    event_player: Option<EventPlayer>,
+    message_sender: Sender<EngineMessage>,
+    message_receiver: Receiver<EngineMessage>,
+    waker: EngineWaker,
+    timers: TimerQueue,
+    wait_for_events: bool,
 }
 
 impl Engine {
@@ -64,6 +91,8 @@ impl Engine {
 
         widget_cache.add(SystemWidget::Base(Box::new(base_widget)));
 
+        let (message_sender, message_receiver) = channel();
+
         Self {
             frame_rate,
             size,
@@ -71,135 +100,320 @@ impl Engine {
             widget_cache,
             font_cache: FontCache::default(),
             event_handler: None,
+            event_recorder: None,
+            event_player: None,
+            message_sender,
+            message_receiver,
+            waker: EngineWaker::default(),
+            timers: TimerQueue::default(),
+            wait_for_events: true,
         }
     }
 
+    /// Sets whether or not the run loop waits for events when idle.  When set (the default), the
+    /// run loop blocks until input, a timer, or a posted message arrives, and only redraws when a
+    /// `Widget` is invalidated, so no CPU time is used while the application is idle.  When
+    /// cleared, the run loop polls for events once per frame instead.
+    pub fn set_wait_for_events(&mut self, wait_for_events: bool) {
+        self.wait_for_events = wait_for_events;
+    }
+
     /// Shuts down the running state, terminating processing.
     pub fn shutdown(&mut self) {
         self.running = false;
     }
 
     /// Adds a new `Widget` to the UI.
-    pub fn add_widget(&mut self, widget: SystemWidget) -> i32 {
+    pub fn add_widget(&mut self, widget: SystemWidget) -> WidgetId {
         self.widget_cache.add(widget)
     }
 
+    /// Adds a new `Widget` to the UI as a child of the `Widget` with the `parent_id` specified.
+    /// The origin of the `Widget` is relative to the origin of its parent.  Returns an
+    /// `InvalidWidget` error if the parent does not exist.
+    pub fn add_child_widget(&mut self, parent_id: WidgetId, widget: SystemWidget) -> Result<WidgetId, PushrodError> {
+        self.widget_cache.add_child(parent_id, widget)
+    }
+
+    /// Removes a `Widget` and all of its children from the UI, returning the removed `Widget`, or
+    /// an `InvalidWidget` error if the `Widget` was not found, or is the top-level `Widget`.
+    pub fn remove_widget(&mut self, widget_id: WidgetId) -> Result<SystemWidget, PushrodError> {
+        self.widget_cache.remove(widget_id)
+    }
+
     /// Adds an event handler to the `Engine` against which generated events will be sent.
     pub fn add_event_handler(&mut self, handler: Box<dyn EventHandler>) {
         self.event_handler = Some(handler);
     }
 
-    /// Returns the current `Size` of the managed window.
+    /// Attaches an `EventRecorder` to the `Engine`, which records every event handled by the
+    /// `Engine` from this point on, including injected events.
+    pub fn set_event_recorder(&mut self, recorder: EventRecorder) {
+        self.event_recorder = Some(recorder);
+    }
+
+    /// Detaches and returns the `EventRecorder` attached to the `Engine`, if any.
+    pub fn take_event_recorder(&mut self) -> Option<EventRecorder> {
+        self.event_recorder.take()
+    }
+
+    /// Attaches an `EventPlayer` to the `Engine`.  When the run loop starts, recorded events are
+    /// injected at the time at which they were recorded, relative to the start of the run loop.
+    pub fn set_event_player(&mut self, player: EventPlayer) {
+        self.event_player = Some(player);
+    }
+
+    /// Injects an `SDL2` `Event` into the `Engine`, handling it exactly as if it had been received
+    /// from the event pump.  Returns `false` if the event was a request to quit, `true` otherwise.
+    pub fn inject_event(&mut self, event: Event) -> bool {
+        self.handle_event(event)
+    }
+
+    /// Returns an `EngineSender`, which can be used to post events and closures to the `Engine`
+    /// from other threads.
+    pub fn get_sender(&self) -> EngineSender {
+        EngineSender::new(self.message_sender.clone(), self.waker.clone())
+    }
+
+    /// Schedules a timer that fires once, after `delay`.  When it fires, a `Timer` event is sent
+    /// to the `target` `Widget`, or to the event handler if `target` is `None`.
+    pub fn add_timer(&mut self, delay: Duration, target: Option<WidgetId>) -> TimerId {
+        self.timers.add(Instant::now(), delay, false, target)
+    }
+
+    /// Schedules a timer that fires every `interval` until it is cancelled.  When it fires, a
+    /// `Timer` event is sent to the `target` `Widget`, or to the event handler if `target` is
+    /// `None`.
+    pub fn add_repeating_timer(&mut self, interval: Duration, target: Option<WidgetId>) -> TimerId {
+        self.timers.add(Instant::now(), interval, true, target)
+    }
+
+    /// Cancels a timer.  Returns `true` if the timer was scheduled, `false` if it had already
+    /// fired, or was already cancelled.
+    pub fn cancel_timer(&mut self, timer_id: TimerId) -> bool {
+        self.timers.cancel(timer_id)
+    }
+
+    /// Returns the current `Size` of the managed window.  This is updated as the window is
+    /// resized.
     pub fn get_size(&self) -> Size {
         self.size
     }
 
-    /// The main run loop.
-    pub fn run(&mut self, sdl: Sdl, window: Window) {
-        // Initializes the canvas, creating a textured canvas against which GPU textures will be
-        // used, specifying hardware acceleration.
-        let mut canvas = window
-            .into_canvas()
-            .target_texture()
-            .accelerated()
-            .build()
-            .unwrap();
-
-        // Sets the screen color to all white, clears and presents the canvas to display.
-        canvas.set_draw_color(Color::RGBA(255, 255, 255, 255));
-        canvas.clear();
-        canvas.present();
-
-        // Attaches to the `SDL` library event pump, against which all windowed events are
-        // sent.  Also determines the amount of time to wait between frames.
-        let mut event_pump = sdl.event_pump().unwrap();
-        let fps_as_ms = (1000.0 / self.frame_rate as f64) as u128;
-
-        // This is the run loop.  It performs the following tasks in the following order:
-        //
-        // First, it pulls all of the events that have been generated by the OS, placed into the event_pump
-        // variable, which is controlled by SDL2.
-        //
-        // Second, events are processed by the WidgetCache, calling "handle_event".  This takes each raw event
-        // produced from the event pump, passes it off to an appropriate widget.  If the Widget produces an event,
-        // that event is captured and loaded into a list of events to be later sent to the registered event handler
-        // attached to the current engine instance.
-        //
-        // Third, generated events are then sent to the event handler after all of the widgets have generated
-        // any events.
-        //
-        // Once processed, the system them sets the drawing color to white, and clears the canvas.  If any of the
-        // Widgets in the draw loop indicate an invalidation state (ie. dirty/redraw state), the system will then
-        // present the canvas after drawing by flipping the buffer to the screen.
-        //
-        // Afterward, if the draw loop was ahead of the screen refresh time, it will wait a certain number of
-        // milliseconds before repeating the loop, making sure that the system keeps within the desired FPS of the
-        // drawing engine.
-        //
-        // If the user ever clicks close on the window, the application will catch the Event::Quit state, and break
-        // out of the run loop, terminating the Engine instance.  If no other engine instances are running, the
-        // application will terminate.
-        'running: loop {
-            let start = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis();
-
-            // Consume and process events first.
-            for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. } => {
-                        break 'running;
-                    }
-
-                    remaining_event => {
-                        let event_result = self.widget_cache.handle_event(remaining_event);
-
-                        if let Some(handler) = &self.event_handler {
-                            // Needs to support handling of multiple events being generated
-                            // here.
-
-                            if !event_result.is_empty() {
-                                handler.process_event(event_result);
-                            }
-                        }
-                    }
-                }
+    /// The main run loop.  Runs until the `Engine` is shut down, or the window is closed.  Returns
+    /// an error if the canvas or event pump could not be created, or the window could not be
+    /// drawn.
+    ///
+    /// The window is run in the same way as a single window of an `Application`, so both share the
+    /// same run loop.
+    pub fn run(&mut self, sdl: Sdl, window: Window) -> Result<(), PushrodError> {
+        let mut windows = vec![ApplicationWindow::new(&sdl, self, window)?];
+
+        run_windows(&sdl, &mut windows)
+    }
+
+    /// Injects any recorded events that have become due since the run loop started, followed by
+    /// any events and closures posted from other threads, and any timers that have become due.
+    /// Returns `false` if a replayed event was a request to quit, `true` otherwise.
+    pub(crate) fn process_pending(&mut self, run_start: Instant) -> bool {
+        let replayed_events = match &mut self.event_player {
+            Some(player) => player.poll_due(run_start.elapsed()),
+            None => Vec::new(),
+        };
+
+        for event in replayed_events {
+            if !self.handle_event(event) {
+                return false;
+            }
+        }
+
+        self.process_messages();
+        self.process_timers();
+
+        true
+    }
+
+    /// Returns the minimum amount of time between frames, based on the frame rate.
+    pub(crate) fn get_frame_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.frame_rate.max(1) as f64)
+    }
+
+    /// Indicates whether or not the run loop waits for events when idle.
+    pub(crate) fn is_waiting_for_events(&self) -> bool {
+        self.wait_for_events
+    }
+
+    /// Returns the amount of time until the next timer or recorded event is due, `None` if
+    /// neither are scheduled.
+    pub(crate) fn get_wait_timeout(&self, run_start: Instant) -> Option<Duration> {
+        let now = Instant::now();
+        let timer_deadline = self.timers.get_next_deadline();
+        let replay_deadline = self
+            .event_player
+            .as_ref()
+            .and_then(|x| x.get_next_due())
+            .map(|x| run_start + x);
+
+        timer_deadline
+            .into_iter()
+            .chain(replay_deadline)
+            .min()
+            .map(|x| x.saturating_duration_since(now))
+    }
+
+    /// Handles a single `SDL2` `Event`, sending it to the `WidgetCache`, and passing any
+    /// `PushrodEvent`s generated as a result to the registered event handler.  Returns `false`
+    /// if the event was a request to quit, `true` otherwise.
+    pub(crate) fn handle_event(&mut self, event: Event) -> bool {
+        if let Some(recorder) = &mut self.event_recorder {
+            if let Err(e) = recorder.record(&event) {
+                warn!(target: "pushrod::event", "Unable to record event: {}", e);
             }
+        }
+
+        // The window is resized before the event is passed on, so that the new size is in place
+        // by the time `Widget`s and the event handler receive the `WindowResized` event.
+        if let Event::Window {
+            win_event: WindowEvent::SizeChanged(w, h),
+            ..
+        } = event
+        {
+            self.resize(Size::new(w.max(0) as u32, h.max(0) as u32));
+        }
+
+        match event {
+            Event::Quit { .. } => false,
+
+            remaining_event => {
+                let event_result = self.widget_cache.handle_event(remaining_event);
+
+                self.dispatch_events(event_result);
+
+                true
+            }
+        }
+    }
+
+    /// Updates the size of the managed window, resizing the top-level `Widget` to match.  The
+    /// scene is recreated at the new size, and every `Widget` is recomposed into it.  Zero-area
+    /// sizes are ignored.
+    fn resize(&mut self, size: Size) {
+        // A minimized window may report a zero-area size, at which textures cannot be created, so
+        // the previous size is kept.
+        if size.w == 0 || size.h == 0 {
+            debug!(target: "pushrod::layout", "Ignoring zero-area resize: size={}x{}", size.w, size.h);
+            return;
+        }
+
+        if self.size != size {
+            debug!(target: "pushrod::layout", "Window resized: size={}x{}", size.w, size.h);
 
-            // TODO TODO TODO
-            // Walk through each event that was generated (if not empty)
-            // call the event_handler and pass each event one-by-one so it can be processed
-            // in the order in which the events were generated.
+            self.size = size;
+            if let Err(e) = self.widget_cache.set_size(WidgetId::default(), size) {
+                warn!(target: "pushrod::layout", "Unable to resize the top-level widget: {}", e);
+            }
+        }
+    }
 
-            // Blit the canvas.
-            canvas.set_draw_color(Color::RGBA(255, 255, 255, 255));
-            canvas.clear();
+    /// Passes each generated event to the event handler one-by-one, in the order in which the
+    /// events were generated.  Events are discarded if no event handler is registered.
+    fn dispatch_events(&mut self, events: Vec<PushrodEvent>) {
+        if let Some(handler) = &mut self.event_handler {
+            let mut context = HandlerContext::new(&mut self.widget_cache, &mut self.timers, &mut self.running);
 
-            // If draw_loop returns a true, indicating invalidation, swap the canvas buffer.  Borrows the font cache
-            // so that a widget can render text if required.
-            if self.widget_cache.draw_loop(&mut canvas, &mut self.font_cache) {
-                canvas.present();
+            for event in events {
+                handler.process_event(event, &mut context);
             }
+        }
+    }
 
-            // This obeys thread sleep time.
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis();
+    /// Enables the `EngineSender`s of this `Engine` to wake the run loop when a message is posted.
+    pub(crate) fn set_waker(&mut self, sdl: &Sdl) {
+        match sdl.event() {
+            Ok(event_subsystem) => *self.waker.lock().unwrap() = Some(event_subsystem.event_sender()),
+            Err(e) => warn!(target: "pushrod::event", "Unable to wake the run loop from other threads: {}", e),
+        }
+    }
 
-            // Sleep a determinant amount of milliseconds to generate enough of a delay for the
-            // frames per second to be honored, so no screen tearing occurs (for vsync)
-            if now - start < fps_as_ms {
-                let diff = fps_as_ms - (now - start);
+    /// Processes any events and closures posted through an `EngineSender`, in the order in which
+    /// they were posted.  Events are passed to the event handler, and closures are run with a
+    /// `HandlerContext`.
+    pub(crate) fn process_messages(&mut self) {
+        while let Ok(message) = self.message_receiver.try_recv() {
+            match message {
+                EngineMessage::Event(event) => {
+                    self.dispatch_events(vec![PushrodEvent::Custom(WidgetId::default(), event)]);
+                }
 
-                sleep(Duration::from_millis(diff as u64));
+                EngineMessage::Call(call) => {
+                    call(&mut HandlerContext::new(&mut self.widget_cache, &mut self.timers, &mut self.running));
+                }
             }
+        }
+    }
+
+    /// Fires any timers that are due.  Each `Timer` event is sent to the target `Widget` of the
+    /// timer, passing any events it generates to the event handler, or directly to the event
+    /// handler if the timer has no target.  Timers whose target `Widget` no longer exists are
+    /// cancelled.
+    pub(crate) fn process_timers(&mut self) {
+        for (timer_id, target) in self.timers.poll_due(Instant::now()) {
+            match target {
+                Some(widget_id) if !self.widget_cache.contains(widget_id) => {
+                    self.timers.cancel(timer_id);
+                }
+
+                Some(widget_id) => {
+                    let events = self.widget_cache.send_event(widget_id, PushrodEvent::Timer(timer_id));
 
-            // Termiante if not running.
-            if !self.running {
-                break 'running;
+                    self.dispatch_events(events);
+                }
+
+                None => self.dispatch_events(vec![PushrodEvent::Timer(timer_id)]),
             }
         }
     }
+
+    /// Composes any damaged or invalidated `Widget`s into the retained scene, and copies it to
+    /// the canvas.  Borrows the font cache so that a widget can render text if required.  Returns
+    /// `true` if anything was drawn, indicating that the canvas needs to be presented, or an error
+    /// if the scene could not be drawn.  The canvas is left untouched if nothing has changed.
+    pub(crate) fn draw(&mut self, canvas: &mut Canvas<Window>) -> Result<bool, PushrodError> {
+        if !self.widget_cache.needs_redraw() {
+            return Ok(false);
+        }
+
+        self.widget_cache.draw_loop(canvas, &mut self.font_cache)
+    }
+
+    /// Returns the `WidgetCache` containing the `Widget`s managed by this `Engine`, which can be
+    /// used to arrange the `Widget`s in the UI.
+    pub fn get_widget_cache(&mut self) -> &mut WidgetCache {
+        &mut self.widget_cache
+    }
+
+    /// Returns the `FontCache` used by `Widget`s to render text.
+    pub(crate) fn get_font_cache(&mut self) -> &mut FontCache {
+        &mut self.font_cache
+    }
+
+    /// Indicates whether or not the `Engine` is still running.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+/// Blocks until an event arrives, or until the `timeout` elapses, returning the event, if any.
+/// Waits indefinitely if no `timeout` is specified.
+pub(crate) fn wait_for_event(event_pump: &mut EventPump, timeout: Option<Duration>) -> Option<Event> {
+    match timeout {
+        Some(timeout) => {
+            // Rounds up to the next millisecond, so that the loop does not wake just before a
+            // timer is due.
+            let timeout_ms = timeout.as_micros().div_ceil(1000).min(u32::MAX as u128);
+
+            event_pump.wait_event_timeout(timeout_ms as u32)
+        }
+        None => Some(event_pump.wait_event()),
+    }
 }
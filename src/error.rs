@@ -0,0 +1,156 @@
+// Errors
+// Pushrod
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # PushrodError
+//!
+//! The error type returned by the fallible functions in `Pushrod`.
+//!
+//! Failures that an application can recover from, such as a font file that does not exist, a
+//! texture that cannot be created, or a `WidgetId` that does not refer to a `Widget`, are returned
+//! as a `PushrodError` rather than causing a panic.  Each variant describes the kind of failure,
+//! and contains the details reported by `SDL2` where available.
+//!
+//! A `Widget` that fails to draw does not stop the draw loop: the error is reported, and the
+//! `Widget` is left undrawn until it is invalidated again.
+
+use crate::geometry::Size;
+use crate::widget::WidgetId;
+use sdl2::render::{TargetRenderError, TextureValueError};
+use sdl2::ttf::FontError;
+use sdl2::video::WindowBuildError;
+use sdl2::IntegerOrSdlError;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Describes a failure reported by `Pushrod`.
+#[derive(Debug)]
+pub enum PushrodError {
+    /// `SDL2` could not be initialized, or a window, canvas or event pump could not be created.
+    Sdl(String),
+
+    /// A font could not be loaded from `path` at the point size specified.
+    FontLoad {
+        path: PathBuf,
+        size: u16,
+        message: String,
+    },
+
+    /// Text could not be measured or rendered using a loaded font.
+    FontRender(String),
+
+    /// A texture of the `Size` specified could not be created.  Textures cannot be zero-sized.
+    TextureCreate { size: Size, message: String },
+
+    /// A drawing operation against a canvas or texture failed.
+    Render(String),
+
+    /// No `Widget` exists with the `WidgetId` specified.
+    InvalidWidget(WidgetId),
+
+    /// The `Engine` that a message was posted to no longer exists.
+    EngineClosed,
+
+    /// A `Snapshot` did not match its golden image.
+    SnapshotMismatch(String),
+
+    /// The pixel data for a `Snapshot` of the `Size` specified did not contain `w * h * 4` bytes.
+    SnapshotSize { size: Size, len: usize },
+
+    /// Reading or writing a file failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for PushrodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushrodError::Sdl(message) => write!(f, "SDL2 error: {}", message),
+            PushrodError::FontLoad { path, size, message } => write!(
+                f,
+                "Unable to load font {} (size={}): {}",
+                path.display(),
+                size,
+                message
+            ),
+            PushrodError::FontRender(message) => write!(f, "Unable to render text: {}", message),
+            PushrodError::TextureCreate { size, message } => write!(
+                f,
+                "Unable to create texture (size={}x{}): {}",
+                size.w, size.h, message
+            ),
+            PushrodError::Render(message) => write!(f, "Unable to draw: {}", message),
+            PushrodError::InvalidWidget(widget_id) => write!(f, "Widget ID {} does not exist", widget_id),
+            PushrodError::EngineClosed => write!(f, "Engine no longer exists"),
+            PushrodError::SnapshotMismatch(message) => write!(f, "{}", message),
+            PushrodError::SnapshotSize { size, len } => write!(
+                f,
+                "Pixel data ({} bytes) does not match the size of the snapshot (size={}x{})",
+                len, size.w, size.h
+            ),
+            PushrodError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl Error for PushrodError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PushrodError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PushrodError {
+    fn from(e: io::Error) -> Self {
+        PushrodError::Io(e)
+    }
+}
+
+impl From<FontError> for PushrodError {
+    fn from(e: FontError) -> Self {
+        PushrodError::FontRender(e.to_string())
+    }
+}
+
+impl From<TargetRenderError> for PushrodError {
+    fn from(e: TargetRenderError) -> Self {
+        PushrodError::Render(e.to_string())
+    }
+}
+
+impl From<WindowBuildError> for PushrodError {
+    fn from(e: WindowBuildError) -> Self {
+        PushrodError::Sdl(e.to_string())
+    }
+}
+
+impl From<IntegerOrSdlError> for PushrodError {
+    fn from(e: IntegerOrSdlError) -> Self {
+        PushrodError::Sdl(e.to_string())
+    }
+}
+
+impl PushrodError {
+    /// Creates a `TextureCreate` error from the error reported by `SDL2` when creating a texture
+    /// of the `Size` specified.
+    pub(crate) fn texture(size: Size, e: TextureValueError) -> Self {
+        PushrodError::TextureCreate {
+            size,
+            message: e.to_string(),
+        }
+    }
+}
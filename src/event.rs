@@ -21,30 +21,362 @@
 //!
 //! `PushrodEvents` are structured objects that contain these messages wrapped in a `Struct`,
 //! which can be interpreted by other `Widget`s in the `Pushrod` library.
+//!
+//! Applications and custom `Widget`s can define their own events using any type as the payload
+//! of a `Custom` event, which is passed through the `WidgetCache` and on to the `EventHandler`
+//! in the same way as the built-in events.
 
+use crate::cache::WidgetCache;
+use crate::error::PushrodError;
+use crate::geometry::Size;
+use crate::timer::{TimerId, TimerQueue};
+use crate::widget::{SystemWidget, Widget, WidgetId};
 use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use std::any::{type_name, Any};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-/// These are different types of events that the `Pushrod` library will generate.  Any custom
-/// events should be added here.
+/// These are different types of events that the `Pushrod` library will generate.  Events that
+/// are specific to an application or a custom `Widget` are sent using the `Custom` variant.
 #[derive(Debug, Clone)]
 pub enum PushrodEvent {
     /// Indicates a change in object bounds, exiting of one bound and entering another.  First
     /// argument is the ID of the `Widget` that lost bounds, second argument is the ID of
     /// the `Widget` that gained bounds.
-    BoundsChange(u32, u32),
+    BoundsChange(WidgetId, WidgetId),
 
     /// Indicates a `Widget` detected a click event inside its bounds.  The first argument is
     /// the ID of the `Widget` that was clicked, second argument is the number of clicks that
     /// the `Widget` received.
-    Clicked(u32, u8),
+    Clicked(WidgetId, u8),
+
+    /// Indicates a `Widget` gained keyboard focus.  The argument is the ID of the `Widget` that
+    /// gained focus.
+    FocusGained(WidgetId),
+
+    /// Indicates a `Widget` lost keyboard focus.  The argument is the ID of the `Widget` that
+    /// lost focus.
+    FocusLost(WidgetId),
+
+    /// Indicates a key was pressed while a `Widget` had keyboard focus.  `keycode` is the
+    /// virtual key pressed, `scancode` is the physical key pressed, `keymod` contains the
+    /// modifier keys held at the time, and `repeat` is set if the event was generated by the
+    /// key being held down.
+    KeyDown {
+        widget_id: WidgetId,
+        keycode: Option<Keycode>,
+        scancode: Option<Scancode>,
+        keymod: Mod,
+        repeat: bool,
+    },
+
+    /// Indicates a key was released while a `Widget` had keyboard focus.  The fields are the same
+    /// as those of `KeyDown`.
+    KeyUp {
+        widget_id: WidgetId,
+        keycode: Option<Keycode>,
+        scancode: Option<Scancode>,
+        keymod: Mod,
+        repeat: bool,
+    },
+
+    /// Indicates text was committed while a `Widget` had keyboard focus.  The first argument is
+    /// the ID of the focused `Widget`, and the second is the text that was entered.  This is the
+    /// event to use for text entry, as it takes the keyboard layout and input methods into
+    /// account.
+    TextInput(WidgetId, String),
+
+    /// Indicates text is being composed by an input method (IME) while a `Widget` had keyboard
+    /// focus.  `text` is the composition in progress, which has not yet been committed, and
+    /// `start` and `length` describe the selected range within it.  Once composition is
+    /// complete, the result is sent as a `TextInput` event.
+    TextEditing {
+        widget_id: WidgetId,
+        text: String,
+        start: i32,
+        length: i32,
+    },
+
+    /// Indicates the mouse wheel was scrolled over a `Widget`.  `x` and `y` are the amount
+    /// scrolled horizontally and vertically, in whole steps, and `precise_x` and `precise_y`
+    /// contain the fractional amounts reported by devices such as trackpads.  Positive `y` values
+    /// scroll away from the user, and positive `x` values scroll to the right, regardless of
+    /// whether the system uses "natural" scrolling.
+    MouseWheel {
+        widget_id: WidgetId,
+        x: i32,
+        y: i32,
+        precise_x: f32,
+        precise_y: f32,
+    },
+
+    /// Indicates the window was resized.  The argument is the new `Size` of the window.
+    WindowResized(Size),
+
+    /// Indicates the window gained keyboard focus from the system.
+    WindowFocusGained,
+
+    /// Indicates the window lost keyboard focus to another window or application.
+    WindowFocusLost,
+
+    /// Indicates a timer fired.  This is sent to the target `Widget` of the timer, or to the
+    /// `EventHandler` if the timer has no target.  The argument is the ID of the timer.
+    Timer(TimerId),
+
+    /// Indicates an application-defined event occurred.  The first argument is the ID of the
+    /// `Widget` that generated the event, and the second is the `CustomEvent` containing its
+    /// payload.
+    Custom(WidgetId, CustomEvent),
 
     /// Indicates an SDL-based Event occurred.  The first argument is the ID of the widget that
     /// was found, and the second is the event that occurred.
-    SystemEvent(u32, Event),
+    SystemEvent(WidgetId, Event),
+}
+
+impl PushrodEvent {
+    /// Creates a new `Custom` event generated by the `Widget` specified, containing `payload`.
+    pub fn custom<T: Any + Send + Sync>(widget_id: WidgetId, payload: T) -> Self {
+        PushrodEvent::Custom(widget_id, CustomEvent::new(payload))
+    }
+
+    /// If this is a `Custom` event with a payload of type `T`, returns the ID of the `Widget`
+    /// that generated it, along with a reference to the payload.  Returns `None` otherwise.
+    pub fn as_custom<T: Any + Send + Sync>(&self) -> Option<(WidgetId, &T)> {
+        match self {
+            PushrodEvent::Custom(widget_id, event) => event.downcast_ref::<T>().map(|x| (*widget_id, x)),
+            _ => None,
+        }
+    }
+}
+
+/// The payload of a `Custom` `PushrodEvent`.  Any type can be used as a payload, so that
+/// applications and custom `Widget`s can define their own events without changing `Pushrod`.
+/// The payload is shared when the event is cloned, and is retrieved by downcasting it to its
+/// original type.
+///
+/// ```rust
+/// # use pushrod::event::PushrodEvent;
+/// # use pushrod::widget::WidgetId;
+/// struct ItemSelected(usize);
+///
+/// let event = PushrodEvent::custom(WidgetId::default(), ItemSelected(3));
+///
+/// if let Some((_, ItemSelected(index))) = event.as_custom::<ItemSelected>() {
+///     assert_eq!(*index, 3);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct CustomEvent {
+    type_name: &'static str,
+    payload: Arc<dyn Any + Send + Sync>,
+}
+
+impl CustomEvent {
+    /// Creates a new `CustomEvent` containing `payload`.
+    pub fn new<T: Any + Send + Sync>(payload: T) -> Self {
+        Self {
+            type_name: type_name::<T>(),
+            payload: Arc::new(payload),
+        }
+    }
+
+    /// Indicates whether or not the payload is of type `T`.
+    pub fn is<T: Any + Send + Sync>(&self) -> bool {
+        self.payload.is::<T>()
+    }
+
+    /// Returns a reference to the payload if it is of type `T`, `None` otherwise.
+    pub fn downcast_ref<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.payload.downcast_ref::<T>()
+    }
+
+    /// Returns the name of the type of the payload, for diagnostic purposes.
+    pub fn get_type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+impl fmt::Debug for CustomEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CustomEvent({})", self.type_name)
+    }
+}
+
+/// The context passed to a `Widget` while it handles an event.  It collects the `PushrodEvent`s
+/// generated by the `Widget`, which are passed on to the `Engine`'s event handler, along with
+/// requests to redraw `Widget`s.  It also provides read-only access to the `WidgetCache`.
+///
+/// While a `Widget` handles an event, it is temporarily removed from the `WidgetCache`, so
+/// looking up its own ID through `get_cache` returns `None`.  Use `self` to access its state.
+pub struct EventContext<'a> {
+    widget_id: WidgetId,
+    cache: &'a WidgetCache,
+    events: Vec<PushrodEvent>,
+    redraw_widget_ids: Vec<WidgetId>,
+}
+
+impl<'a> EventContext<'a> {
+    /// Creates a new `EventContext` for the `Widget` with the ID specified.
+    pub(crate) fn new(widget_id: WidgetId, cache: &'a WidgetCache) -> Self {
+        Self {
+            widget_id,
+            cache,
+            events: Vec::new(),
+            redraw_widget_ids: Vec::new(),
+        }
+    }
+
+    /// Retrieves the ID of the `Widget` handling the event.
+    pub fn get_widget_id(&self) -> WidgetId {
+        self.widget_id
+    }
+
+    /// Retrieves the `WidgetCache`, so that other `Widget`s can be queried.
+    pub fn get_cache(&self) -> &WidgetCache {
+        self.cache
+    }
+
+    /// Adds a `PushrodEvent` to the list of events generated while handling the event.  Events
+    /// are passed to the `Engine`'s event handler in the order in which they were pushed.
+    pub fn push_event(&mut self, event: PushrodEvent) {
+        self.events.push(event);
+    }
+
+    /// Requests that the `Widget` handling the event is redrawn.
+    pub fn request_redraw(&mut self) {
+        self.redraw_widget_ids.push(self.widget_id);
+    }
+
+    /// Requests that another `Widget` is redrawn, such as a `Widget` whose contents depend on the
+    /// state of the `Widget` handling the event.
+    pub fn request_redraw_of(&mut self, widget_id: WidgetId) {
+        self.redraw_widget_ids.push(widget_id);
+    }
+
+    /// Consumes the `EventContext`, returning the generated events, and the IDs of the `Widget`s
+    /// to redraw.
+    pub(crate) fn into_parts(self) -> (Vec<PushrodEvent>, Vec<WidgetId>) {
+        (self.events, self.redraw_widget_ids)
+    }
+}
+
+/// The context passed to an `EventHandler` while it processes an event.  It provides mutable
+/// access to the `Widget`s in the `WidgetCache`, so that the UI can be changed in response to an
+/// event, allows timers to be scheduled, and allows the `Engine` to be shut down.
+///
+/// A `Widget` retrieved through `get_widget_mut` or `get_widget_as` may be moved, resized or
+/// hidden directly, so the area it occupies is damaged when it is retrieved, and again when the
+/// `HandlerContext` is dropped, so that both its previous and its new areas are redrawn.
+pub struct HandlerContext<'a> {
+    widget_cache: &'a mut WidgetCache,
+    timers: &'a mut TimerQueue,
+    running: &'a mut bool,
+    borrowed_widget_ids: Vec<WidgetId>,
+}
+
+impl<'a> HandlerContext<'a> {
+    /// Creates a new `HandlerContext` against the `WidgetCache`, timers and running flag of an
+    /// `Engine`.
+    pub(crate) fn new(widget_cache: &'a mut WidgetCache, timers: &'a mut TimerQueue, running: &'a mut bool) -> Self {
+        Self {
+            widget_cache,
+            timers,
+            running,
+            borrowed_widget_ids: Vec::new(),
+        }
+    }
+
+    /// Retrieves the `WidgetCache`, so that `Widget`s can be arranged, hidden, focused, and so on.
+    pub fn get_widget_cache(&mut self) -> &mut WidgetCache {
+        self.widget_cache
+    }
+
+    /// Retrieves a mutable reference to a `Widget` by ID, `None` if not found.  The area the
+    /// `Widget` occupies is damaged now, and again once the event has been processed.
+    pub fn get_widget_mut(&mut self, widget_id: WidgetId) -> Option<&mut dyn Widget> {
+        if !self.borrowed_widget_ids.contains(&widget_id) {
+            self.widget_cache.damage_widget(widget_id);
+            self.borrowed_widget_ids.push(widget_id);
+        }
+
+        self.widget_cache
+            .get_mut(widget_id)
+            .map(|x| x.as_widget_mut())
+    }
+
+    /// Retrieves a mutable reference to a `Widget` by ID, downcast to its concrete type.  Returns
+    /// `None` if the `Widget` was not found, or is not of type `T`.
+    ///
+    /// ```rust,no_run
+    /// # use pushrod::event::HandlerContext;
+    /// # use pushrod::text_widget::TextWidget;
+    /// # use pushrod::widget::WidgetId;
+    /// # fn update(context: &mut HandlerContext, label_id: WidgetId) {
+    /// if let Some(label) = context.get_widget_as::<TextWidget>(label_id) {
+    ///     label.set_text(String::from("Clicked!"));
+    /// }
+    /// # }
+    /// ```
+    pub fn get_widget_as<T: Widget + 'static>(&mut self, widget_id: WidgetId) -> Option<&mut T> {
+        self.get_widget_mut(widget_id)?.as_any_mut().downcast_mut::<T>()
+    }
+
+    /// Adds a new `Widget` to the UI, returning its ID.
+    pub fn add_widget(&mut self, widget: SystemWidget) -> WidgetId {
+        self.widget_cache.add(widget)
+    }
+
+    /// Adds a new `Widget` to the UI as a child of the `Widget` with the `parent_id` specified,
+    /// returning its ID, or an `InvalidWidget` error if the parent does not exist.
+    pub fn add_child_widget(&mut self, parent_id: WidgetId, widget: SystemWidget) -> Result<WidgetId, PushrodError> {
+        self.widget_cache.add_child(parent_id, widget)
+    }
+
+    /// Removes a `Widget` and all of its children from the UI, returning the removed `Widget`, or
+    /// an `InvalidWidget` error if the `Widget` was not found, or is the top-level `Widget`.
+    pub fn remove_widget(&mut self, widget_id: WidgetId) -> Result<SystemWidget, PushrodError> {
+        self.widget_cache.remove(widget_id)
+    }
+
+    /// Schedules a timer that fires once, after `delay`.  When it fires, a `Timer` event is sent
+    /// to the `target` `Widget`, or to the `EventHandler` if `target` is `None`.
+    pub fn add_timer(&mut self, delay: Duration, target: Option<WidgetId>) -> TimerId {
+        self.timers.add(Instant::now(), delay, false, target)
+    }
+
+    /// Schedules a timer that fires every `interval` until it is cancelled.  When it fires, a
+    /// `Timer` event is sent to the `target` `Widget`, or to the `EventHandler` if `target` is
+    /// `None`.
+    pub fn add_repeating_timer(&mut self, interval: Duration, target: Option<WidgetId>) -> TimerId {
+        self.timers.add(Instant::now(), interval, true, target)
+    }
+
+    /// Cancels a timer.  Returns `true` if the timer was scheduled, `false` otherwise.
+    pub fn cancel_timer(&mut self, timer_id: TimerId) -> bool {
+        self.timers.cancel(timer_id)
+    }
+
+    /// Shuts down the `Engine`.  The run loop terminates once the current events are processed.
+    pub fn shutdown(&mut self) {
+        *self.running = false;
+    }
+}
+
+impl Drop for HandlerContext<'_> {
+    /// Damages the current areas of the `Widget`s retrieved through `get_widget_mut`, in case
+    /// they were moved or resized.
+    fn drop(&mut self) {
+        for widget_id in self.borrowed_widget_ids.drain(..) {
+            self.widget_cache.damage_widget(widget_id);
+        }
+    }
 }
 
 /// This is a trait that indicates an impl can process events.
 pub trait EventHandler {
-    /// Processes a list of events.
-    fn process_event(&self, events: Vec<&PushrodEvent>);
+    /// Processes a single event generated by a `Widget`.  Events are processed in the order in
+    /// which they were generated.  The `HandlerContext` can be used to change the UI in response.
+    fn process_event(&mut self, event: PushrodEvent, context: &mut HandlerContext);
 }
@@ -13,23 +13,478 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::path::Path;
-use sdl2::pixels::Color;
-use sdl2::render::{Canvas, Texture, TextureQuery};
-use sdl2::ttf::{FontStyle, Sdl2TtfContext};
+//! # FontCache
+//!
+//! Loads and caches the fonts used by `Widget`s to render text.
+//!
+//! Fonts are loaded once for each combination of font name and point size, and are kept in the
+//! cache for subsequent renders, rather than being reopened and reparsed every time text is
+//! drawn.  A font name is either the path to a `TTF` file, or a family name registered using
+//! `register_family`, which maps a logical name such as `"body"` to the path of a `TTF` file.
+//! Fonts can be loaded ahead of time using `preload`.
+//!
+//! Text can be drawn in two ways.  `render_text` rasterizes a whole string into a new `Texture`,
+//! wrapping it to a width, which suits text that rarely changes.  `draw_text` draws a string
+//! directly to the current target of a `Canvas`, using a glyph atlas: each glyph is rasterized
+//! once into a shared texture for its font, size and style, and strings are drawn by copying
+//! glyphs from the atlas, adjusted for kerning.  This suits text that changes often, such as
+//! counters, clocks and logs.
+//!
+//! Text can be measured before it is drawn, using `measure_text` and `measure_wrapped_text`.
+//! `get_font_metrics` returns the vertical metrics of a font, and `get_char_offsets` returns the
+//! horizontal position of each character in a string, for placing a caret.
+//!
+//! The cache holds a limited number of fonts.  Once the limit is reached, loading another font
+//! evicts the font that was least recently used.  Fonts can also be evicted explicitly using
+//! `unload` and `clear`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, Texture, TextureQuery};
+use sdl2::ttf::{Font, FontStyle, Sdl2TtfContext};
 use sdl2::video::Window;
+use crate::error::PushrodError;
+use crate::geometry::{Point, Size};
+
+/// Default number of fonts kept in the `FontCache` before the least recently used font is evicted.
+const DEFAULT_FONT_CAPACITY: usize = 32;
+
+/// Width and height of each page of a glyph atlas, in pixels.
+const ATLAS_PAGE_SIZE: u32 = 512;
+
+/// Number of bytes in a single pixel of a glyph atlas page.
+const ATLAS_BYTES_PER_PIXEL: usize = 4;
+
+/// A glyph stored in a `GlyphAtlas`: the page and area of the atlas containing it, the horizontal
+/// offset at which it is drawn relative to the pen position, and the distance to advance the pen.
+#[derive(Copy, Clone)]
+struct Glyph {
+    page: usize,
+    rect: Option<Rect>,
+    offset_x: i32,
+    advance: i32,
+}
+
+/// The glyphs rasterized for a single font, size and style, stored in pages of GPU textures.
+/// Glyphs are rasterized in white, and colored when drawn.  Kerning between pairs of glyphs is
+/// measured once, and cached along with the glyphs.
+#[derive(Default)]
+struct GlyphAtlas {
+    pages: Vec<Texture>,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), i32>,
+    cursor: Point,
+    row_height: u32,
+}
+
+impl GlyphAtlas {
+    /// Destroys the pages of the atlas, releasing their GPU memory.  As the `unsafe_textures`
+    /// feature is enabled, the pages are not destroyed when the atlas is dropped.
+    fn destroy(self) {
+        for page in self.pages {
+            unsafe {
+                page.destroy();
+            }
+        }
+    }
+
+    /// Retrieves a glyph, rasterizing it into the atlas if it has not been drawn before.
+    fn get_glyph(&mut self, c: &mut Canvas<Window>, font: &Font, ch: char) -> Result<Glyph, PushrodError> {
+        if let Some(glyph) = self.glyphs.get(&ch) {
+            return Ok(*glyph);
+        }
+
+        let metrics = font.find_glyph_metrics(ch);
+        let advance = metrics.as_ref().map_or(0, |x| x.advance);
+
+        // Glyphs are rasterized with the pen at their origin, so glyphs that extend to the left
+        // of the pen are shifted right within their surface.
+        let offset_x = metrics.as_ref().map_or(0, |x| x.minx.min(0));
+        let surface = font
+            .render_char(ch)
+            .blended(Color::RGBA(255, 255, 255, 255))?
+            .convert_format(PixelFormatEnum::ARGB8888)
+            .map_err(PushrodError::FontRender)?;
+        let (w, h) = (surface.width(), surface.height());
+
+        // Glyphs that draw nothing, such as spaces, only advance the pen.  Glyphs that are too
+        // large to fit in a page are skipped.
+        if w == 0 || h == 0 || w > ATLAS_PAGE_SIZE || h > ATLAS_PAGE_SIZE {
+            let glyph = Glyph {
+                page: 0,
+                rect: None,
+                offset_x,
+                advance,
+            };
+
+            self.glyphs.insert(ch, glyph);
+
+            return Ok(glyph);
+        }
+
+        if self.cursor.x as u32 + w > ATLAS_PAGE_SIZE {
+            self.cursor = Point::new(0, self.cursor.y + self.row_height as i32);
+            self.row_height = 0;
+        }
+
+        if self.pages.is_empty() || self.cursor.y as u32 + h > ATLAS_PAGE_SIZE {
+            self.pages.push(Self::create_page(c)?);
+            self.cursor = Point::new(0, 0);
+            self.row_height = 0;
+        }
+
+        let rect = Rect::new(self.cursor.x, self.cursor.y, w, h);
+        let page = self.pages.len() - 1;
+        let pitch = surface.pitch() as usize;
+        let texture = &mut self.pages[page];
+
+        surface
+            .with_lock(|data| texture.update(rect, data, pitch))
+            .map_err(|e| PushrodError::Render(e.to_string()))?;
+
+        self.cursor.x += w as i32;
+        self.row_height = self.row_height.max(h);
+
+        let glyph = Glyph {
+            page,
+            rect: Some(rect),
+            offset_x,
+            advance,
+        };
+
+        self.glyphs.insert(ch, glyph);
+
+        Ok(glyph)
+    }
+
+    /// Retrieves the kerning adjustment between two glyphs, in pixels, measuring it the first time
+    /// the pair is drawn.
+    fn get_kerning(&mut self, font: &Font, previous: char, ch: char) -> i32 {
+        *self
+            .kerning
+            .entry((previous, ch))
+            .or_insert_with(|| measure_kerning(font, previous, ch))
+    }
+
+    /// Internal function that creates a new, transparent page for the atlas.
+    fn create_page(c: &mut Canvas<Window>) -> Result<Texture, PushrodError> {
+        let mut texture = c
+            .texture_creator()
+            .create_texture_static(PixelFormatEnum::ARGB8888, ATLAS_PAGE_SIZE, ATLAS_PAGE_SIZE)
+            .map_err(|e| PushrodError::texture(Size::new(ATLAS_PAGE_SIZE, ATLAS_PAGE_SIZE), e))?;
+        let pitch = ATLAS_PAGE_SIZE as usize * ATLAS_BYTES_PER_PIXEL;
+        let pixels = vec![0; pitch * ATLAS_PAGE_SIZE as usize];
+
+        texture
+            .update(None, &pixels, pitch)
+            .map_err(|e| PushrodError::Render(e.to_string()))?;
+        texture.set_blend_mode(BlendMode::Blend);
+
+        Ok(texture)
+    }
+}
+
+/// The vertical metrics of a font, in pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FontMetrics {
+    /// Maximum height of a glyph, from the lowest descent to the highest ascent.
+    pub height: i32,
+
+    /// Recommended distance between the baselines of two lines of text.
+    pub line_height: i32,
+
+    /// Distance from the baseline to the top of the highest glyph.
+    pub ascent: i32,
+
+    /// Distance from the baseline to the bottom of the lowest glyph.  This is usually negative.
+    pub descent: i32,
+}
+
+/// A font loaded into the `FontCache`, along with the last time that it was used.
+struct CachedFont {
+    font: Font<'static, 'static>,
+    last_used: u64,
+}
 
+/// Contains the `TTF` context once initialized, the loaded fonts keyed by path and point size, and the registered
+/// font families.
 pub struct FontCache {
-    ttf_context: Sdl2TtfContext,
+    ttf_context: Option<&'static Sdl2TtfContext>,
+    fonts: HashMap<(PathBuf, u16), CachedFont>,
+    families: HashMap<String, PathBuf>,
+    atlases: HashMap<(PathBuf, u16, i32), GlyphAtlas>,
+    capacity: usize,
+    use_counter: u64,
 }
 
 impl FontCache {
+    /// Creates a new `FontCache`.  The `TTF` library is initialized when the first font is
+    /// loaded, so that a failure to initialize it is reported as an error at that point.
     pub fn new() -> Self {
         Self {
-            ttf_context: sdl2::ttf::init().map_err(|e| e.to_string()).unwrap(),
+            ttf_context: None,
+            fonts: HashMap::new(),
+            families: HashMap::new(),
+            atlases: HashMap::new(),
+            capacity: DEFAULT_FONT_CAPACITY,
+            use_counter: 0,
+        }
+    }
+
+    /// Registers a font family, so that the font at `path` can be referred to by `family` in
+    /// place of its path.  Registering a family again replaces its path.
+    pub fn register_family<P: AsRef<Path>>(&mut self, family: &str, path: P) {
+        self.families
+            .insert(family.to_string(), path.as_ref().to_path_buf());
+    }
+
+    /// Loads a font into the cache ahead of time, so that it does not need to be loaded the
+    /// first time it is drawn.  Returns an error if the font could not be loaded.
+    pub fn preload(&mut self, font_name: &str, font_size: u16) -> Result<(), PushrodError> {
+        self.get_font(font_name, font_size).map(|_| ())
+    }
+
+    /// Removes a font from the cache, destroying the textures of its glyph atlases.  Returns
+    /// `true` if the font was loaded, `false` otherwise.
+    pub fn unload(&mut self, font_name: &str, font_size: u16) -> bool {
+        let key = (self.resolve_path(font_name), font_size);
+
+        self.remove_font(&key)
+    }
+
+    /// Removes every font from the cache, destroying the textures of every glyph atlas.
+    /// Registered font families are kept.
+    pub fn clear(&mut self) {
+        self.fonts.clear();
+
+        for (_, atlas) in self.atlases.drain() {
+            atlas.destroy();
+        }
+    }
+
+    /// Sets the maximum number of fonts kept in the cache, evicting the least recently used fonts
+    /// if the cache currently holds more.  A capacity of `0` is treated as `1`.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+
+        while self.fonts.len() > self.capacity {
+            self.evict_least_recently_used();
+        }
+    }
+
+    /// Returns the number of fonts currently loaded in the cache.
+    pub fn get_loaded_count(&self) -> usize {
+        self.fonts.len()
+    }
+
+    /// Internal function that resolves a font name to the path of a font file, using the
+    /// registered font families.  Names that are not registered families are treated as paths.
+    fn resolve_path(&self, font_name: &str) -> PathBuf {
+        match self.families.get(font_name) {
+            Some(path) => path.clone(),
+            None => PathBuf::from(font_name),
+        }
+    }
+
+    /// Internal function that removes the least recently used font from the cache.
+    fn evict_least_recently_used(&mut self) {
+        let key = self
+            .fonts
+            .iter()
+            .min_by_key(|(_, x)| x.last_used)
+            .map(|(key, _)| key.clone());
+
+        if let Some(key) = key {
+            self.remove_font(&key);
         }
     }
 
+    /// Internal function that removes a font, and the glyph atlases rasterized from it.
+    fn remove_font(&mut self, key: &(PathBuf, u16)) -> bool {
+        let atlas_keys: Vec<(PathBuf, u16, i32)> = self
+            .atlases
+            .keys()
+            .filter(|(path, size, _)| path == &key.0 && *size == key.1)
+            .cloned()
+            .collect();
+
+        for atlas_key in atlas_keys {
+            if let Some(atlas) = self.atlases.remove(&atlas_key) {
+                atlas.destroy();
+            }
+        }
+
+        self.fonts.remove(key).is_some()
+    }
+
+    /// Internal function that retrieves the `TTF` context, initializing the `TTF` library the
+    /// first time that it is called.
+    fn get_ttf_context(&mut self) -> Result<&'static Sdl2TtfContext, PushrodError> {
+        if let Some(ttf_context) = self.ttf_context {
+            return Ok(ttf_context);
+        }
+
+        // Loaded fonts borrow the `TTF` context, so it must outlive every `FontCache`.  The context
+        // holds no data, so leaking it costs nothing, and only skips the final `TTF_Quit`.
+        let ttf_context: &'static Sdl2TtfContext =
+            Box::leak(Box::new(sdl2::ttf::init().map_err(|e| PushrodError::Sdl(e.to_string()))?));
+
+        self.ttf_context = Some(ttf_context);
+
+        Ok(ttf_context)
+    }
+
+    /// Internal function that retrieves a font from the cache, loading it if it is not already
+    /// loaded.  Returns an error if the font could not be loaded.
+    fn get_font(&mut self, font_name: &str, font_size: u16) -> Result<&mut Font<'static, 'static>, PushrodError> {
+        let key = (self.resolve_path(font_name), font_size);
+
+        self.use_counter += 1;
+
+        if !self.fonts.contains_key(&key) {
+            let font = self
+                .get_ttf_context()?
+                .load_font(&key.0, font_size)
+                .map_err(|message| PushrodError::FontLoad {
+                    path: key.0.clone(),
+                    size: font_size,
+                    message,
+                })?;
+
+            if self.fonts.len() >= self.capacity {
+                self.evict_least_recently_used();
+            }
+
+            self.fonts.insert(
+                key.clone(),
+                CachedFont {
+                    font,
+                    last_used: 0,
+                },
+            );
+        }
+
+        let cached_font = self.fonts.get_mut(&key).unwrap();
+
+        cached_font.last_used = self.use_counter;
+
+        Ok(&mut cached_font.font)
+    }
+
+    /// Returns the vertical metrics of the font specified, or an error if the font could
+    /// not be loaded.
+    pub fn get_font_metrics(&mut self, font_name: &str, font_size: u16) -> Result<FontMetrics, PushrodError> {
+        let font = self.get_font(font_name, font_size)?;
+
+        Ok(FontMetrics {
+            height: font.height(),
+            line_height: font.recommended_line_spacing(),
+            ascent: font.ascent(),
+            descent: font.descent(),
+        })
+    }
+
+    /// Measures the `Size` of `text` drawn as a single line, without drawing it.  Returns an error
+    /// message if the font could not be loaded.
+    pub fn measure_text(
+        &mut self,
+        font_name: &str,
+        font_size: u16,
+        font_style: FontStyle,
+        text: &str,
+    ) -> Result<Size, PushrodError> {
+        let font = self.get_font(font_name, font_size)?;
+
+        font.set_style(font_style);
+
+        // Empty strings have no width, but still occupy the height of a line.
+        if text.is_empty() {
+            return Ok(Size::new(0, font.height().max(0) as u32));
+        }
+
+        let (w, h) = font.size_of(text)?;
+
+        Ok(Size::new(w, h))
+    }
+
+    /// Measures the `Size` of `text` wrapped to `width` pixels, as it would be drawn by
+    /// `render_text`, without creating a `Texture`.  The text is wrapped by `SDL_ttf` in the same
+    /// way as `render_text`, so the text is rasterized in order to be measured: cache the result
+    /// rather than measuring the same text repeatedly.  Empty text measures as a single empty line.
+    /// Returns an error if the font could not be loaded, or the text could not be rasterized.
+    pub fn measure_wrapped_text(
+        &mut self,
+        font_name: &str,
+        font_size: u16,
+        font_style: FontStyle,
+        text: &str,
+        width: u32,
+    ) -> Result<Size, PushrodError> {
+        let font = self.get_font(font_name, font_size)?;
+
+        font.set_style(font_style);
+
+        if text.is_empty() {
+            return Ok(Size::new(0, font.height().max(0) as u32));
+        }
+
+        let surface = font
+            .render(text)
+            .blended_wrapped(Color::RGBA(255, 255, 255, 255), width)?;
+
+        Ok(Size::new(surface.width(), surface.height()))
+    }
+
+    /// Returns the horizontal offset of each character boundary in `text` drawn as a single
+    /// line, in pixels from the start of the line.  The list contains one more offset than there
+    /// are characters: the first offset is `0`, the offset at index `n` is the position of a
+    /// caret placed before the `n`th character, and the last offset is the position after the
+    /// final character.  Kerning is applied in the same way as `measure_text`, so for text that
+    /// does not start or end with an overhanging glyph, the last offset is the width returned by
+    /// `measure_text`.  Returns an error if the font could not be loaded.
+    pub fn get_char_offsets(
+        &mut self,
+        font_name: &str,
+        font_size: u16,
+        font_style: FontStyle,
+        text: &str,
+    ) -> Result<Vec<i32>, PushrodError> {
+        let font = self.get_font(font_name, font_size)?;
+
+        font.set_style(font_style);
+
+        let mut offsets = Vec::with_capacity(text.chars().count() + 1);
+        let mut pen_x = 0;
+        let mut previous = None;
+
+        offsets.push(0);
+
+        for ch in text.chars() {
+            if let Some(previous) = previous {
+                pen_x += measure_kerning(font, previous, ch);
+
+                // The kerning adjustment moves the start of this character, so the caret before
+                // it moves along with it.
+                if let Some(last) = offsets.last_mut() {
+                    *last = pen_x;
+                }
+            }
+
+            pen_x += font.find_glyph_metrics(ch).map_or(0, |x| x.advance);
+            offsets.push(pen_x);
+            previous = Some(ch);
+        }
+
+        Ok(offsets)
+    }
+
+    /// Renders `text` to a new `Texture` using the font specified, wrapping the text at `width`
+    /// pixels.  Returns the `Texture`, along with its width and height, or an error if the font
+    /// could not be loaded, or the text could not be rendered.  Empty text cannot be rendered, and
+    /// returns an error.  The `Texture` must be destroyed once it is no longer needed.
+    #[allow(clippy::too_many_arguments)]
     pub fn render_text(
         &mut self,
         c: &mut Canvas<Window>,
@@ -39,28 +494,104 @@ impl FontCache {
         font_color: Color,
         text: String,
         width: u32,
-    ) -> (Texture, u32, u32) {
+    ) -> Result<(Texture, u32, u32), PushrodError> {
         let texture_creator = c.texture_creator();
-        let mut font = self.ttf_context
-            .load_font(Path::new(&font_name), font_size)
-            .unwrap();
+        let font = self.get_font(&font_name, font_size)?;
 
         font.set_style(font_style);
-
-        let surface = font
-            .render(&text)
-            .blended_wrapped(font_color, width)
-            .map_err(|e| e.to_string())
-            .unwrap();
+        let surface = font.render(&text).blended_wrapped(font_color, width)?;
         let font_texture = texture_creator
             .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())
-            .unwrap();
+            .map_err(|e| PushrodError::texture(Size::new(surface.width(), surface.height()), e))?;
 
         let TextureQuery { width, height, .. } = font_texture.query();
 
-        (font_texture, width, height)
+        Ok((font_texture, width, height))
     }
+
+    /// Draws `text` to the current target of the `Canvas`, with the pen starting at `origin`,
+    /// using the glyph atlas for the font specified.  Each line of `text` is drawn below the
+    /// previous one.  Glyphs are rasterized into the atlas the first time they are drawn, so
+    /// subsequent draws only copy from the atlas.  Returns the `Size` of the area covered by the
+    /// text, or an error if the font could not be loaded.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_text(
+        &mut self,
+        c: &mut Canvas<Window>,
+        font_name: &str,
+        font_size: u16,
+        font_style: FontStyle,
+        font_color: Color,
+        text: &str,
+        origin: Point,
+    ) -> Result<Size, PushrodError> {
+        self.get_font(font_name, font_size)?.set_style(font_style);
+
+        // The font and its atlas are borrowed separately, so that glyphs can be rasterized from
+        // the font into the atlas.
+        let key = (self.resolve_path(font_name), font_size);
+        let font: &Font = &self.fonts[&key].font;
+        let line_height = font.recommended_line_spacing().max(font.height());
+        let atlas = self
+            .atlases
+            .entry((key.0, key.1, font_style.bits()))
+            .or_default();
+        let mut size = Size::new(0, 0);
+
+        for (line_number, line) in text.split('\n').enumerate() {
+            let y = origin.y + line_number as i32 * line_height;
+            let mut pen_x = 0;
+            let mut previous = None;
+
+            for ch in line.chars() {
+                if let Some(previous) = previous {
+                    pen_x += atlas.get_kerning(font, previous, ch);
+                }
+
+                let glyph = atlas.get_glyph(c, font, ch)?;
+
+                if let Some(rect) = glyph.rect {
+                    let page = &mut atlas.pages[glyph.page];
+
+                    page.set_color_mod(font_color.r, font_color.g, font_color.b);
+                    page.set_alpha_mod(font_color.a);
+
+                    c.copy(
+                        page,
+                        rect,
+                        Rect::new(origin.x + pen_x + glyph.offset_x, y, rect.width(), rect.height()),
+                    )
+                    .map_err(PushrodError::Render)?;
+                }
+
+                pen_x += glyph.advance;
+                previous = Some(ch);
+            }
+
+            size.w = size.w.max(pen_x.max(0) as u32);
+            size.h = ((line_number as i32 + 1) * line_height) as u32;
+        }
+
+        Ok(size)
+    }
+}
+
+/// Measures the kerning adjustment between two glyphs, in pixels.  `SDL2_ttf` applies kerning
+/// when measuring strings, so the adjustment is the difference between the width of the pair, and
+/// the width of the second glyph after advancing past the first.
+///
+/// The width of a string includes the negative left bearing of its first glyph.  Measured alone,
+/// the second glyph includes its own bearing, but within the pair it is covered by the first
+/// glyph, so the bearings are corrected for, leaving only the kerning.
+fn measure_kerning(font: &Font, previous: char, ch: char) -> i32 {
+    let pair_width = font.size_of(&format!("{}{}", previous, ch)).map_or(0, |x| x.0 as i32);
+    let width = font.size_of(&ch.to_string()).map_or(0, |x| x.0 as i32);
+    let previous_metrics = font.find_glyph_metrics(previous);
+    let advance = previous_metrics.as_ref().map_or(0, |x| x.advance);
+    let previous_bearing = previous_metrics.as_ref().map_or(0, |x| x.minx.min(0));
+    let bearing = font.find_glyph_metrics(ch).map_or(0, |x| x.minx.min(0));
+
+    pair_width - advance - width + previous_bearing - bearing
 }
 
 impl Default for FontCache {
@@ -68,3 +599,42 @@ impl Default for FontCache {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT_NAME: &str = "assets/OpenSans-Regular.ttf";
+
+    #[test]
+    fn test_unkerned_pairs_measure_zero() {
+        let mut font_cache = FontCache::new();
+        let font = font_cache.get_font(FONT_NAME, 14).unwrap();
+
+        font.set_kerning(false);
+
+        // `j` has a negative left bearing, which must not be mistaken for kerning.
+        for (previous, ch) in [('a', 'b'), ('i', 'j'), ('a', 'j'), ('j', 'a'), ('j', 'j')] {
+            assert_eq!(measure_kerning(font, previous, ch), 0, "pair={}{}", previous, ch);
+        }
+    }
+
+    #[test]
+    fn test_char_offsets_match_measure_text() {
+        let mut font_cache = FontCache::new();
+
+        // The text includes glyphs with negative left bearings, and commonly kerned pairs.
+        for text in ["Hello, jumping World", "WAVY Tokyo", "xjyjx"] {
+            let offsets = font_cache
+                .get_char_offsets(FONT_NAME, 14, FontStyle::NORMAL, text)
+                .unwrap();
+            let size = font_cache
+                .measure_text(FONT_NAME, 14, FontStyle::NORMAL, text)
+                .unwrap();
+
+            assert_eq!(offsets.len(), text.chars().count() + 1);
+            assert_eq!(offsets[0], 0);
+            assert_eq!(*offsets.last().unwrap(), size.w as i32, "text={:?}", text);
+        }
+    }
+}
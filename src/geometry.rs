@@ -23,7 +23,7 @@
 use sdl2::rect::Rect;
 
 /// A geometric struct representing `x` and `y` positional coordinates.
-#[derive(Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 pub struct Point {
     /// Horizontal point from left to right in pixels.
     pub x: i32,
@@ -45,7 +45,7 @@ pub fn point(x: i32, y: i32) -> Point {
 }
 
 /// A geometric struct representing the size of an object in `w`idth and `h`eight.
-#[derive(Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 pub struct Size {
     /// Width in pixels.
     pub w: u32,
@@ -0,0 +1,246 @@
+// Headless Engine
+// Pushrod
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # HeadlessEngine
+//!
+//! Runs an `Engine` without a display.  This is intended for environments such as CI containers,
+//! where no windowing system is available.
+//!
+//! The `HeadlessEngine` initializes `SDL2` using the `offscreen` video driver, and renders
+//! into a hidden `Window` using the software renderer.  Since the canvas is still a
+//! `Canvas<Window>`, the `WidgetCache` draw loop and each `Widget`'s `draw` function work
+//! unchanged against it.
+//!
+//! Rather than blocking in a run loop, the `HeadlessEngine` is driven by calling `step`, which
+//! processes any pending events and draws a single frame.  The resulting pixels can then be read
+//! back using `read_pixels`.
+//!
+//! `SDL2` can only be used from one thread at a time, and the test harness runs tests on
+//! several threads in parallel.  Only one `HeadlessEngine` exists in a process at a time: creating
+//! one blocks until any `HeadlessEngine` on another thread has been dropped, so tests that use
+//! it are serialized automatically, and do not need to be run with `--test-threads=1`.  Creating a
+//! second `HeadlessEngine` on a thread that already has one returns an error, rather than
+//! blocking forever.
+//!
+//! ```rust,no_run
+//! # use pushrod::engine::Engine;
+//! # use pushrod::geometry::size;
+//! # use pushrod::headless::HeadlessEngine;
+//! let mut headless = HeadlessEngine::new(Engine::new(size(800, 600), 60)).unwrap();
+//!
+//! headless.step();
+//!
+//! let pixels = headless.read_pixels().unwrap();
+//! ```
+//!
+//! Events can be injected directly using `inject_event`, or replayed from a recording made with
+//! an `EventRecorder` using `replay`.
+//!
+//! Note, the `offscreen` video driver requires `SDL2` version 2.0.22 or later to be selected
+//! through a hint.  For older versions, set the `SDL_VIDEODRIVER` environment variable to
+//! `offscreen` before running.
+
+use crate::engine::Engine;
+use crate::error::PushrodError;
+use crate::font::FontCache;
+use crate::recorder::EventPlayer;
+use log::{error, warn};
+use sdl2::event::Event;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+use sdl2::{EventPump, Sdl, VideoSubsystem};
+use std::cell::Cell;
+use std::sync::{Mutex, MutexGuard};
+
+/// Held by the `HeadlessEngine` that is currently using `SDL2`.
+static HEADLESS_LOCK: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    /// Set while the current thread holds the `HEADLESS_LOCK`.
+    static HOLDS_HEADLESS_LOCK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Serializes the use of `SDL2` between `HeadlessEngine`s on different threads.  `SDL2` allows
+/// another thread to initialize it once every handle from the previous thread has been dropped,
+/// so the lock is released after the `SDL2` handles of the `HeadlessEngine`.
+struct HeadlessLock {
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl HeadlessLock {
+    /// Acquires the lock, waiting for any `HeadlessEngine` on another thread to be dropped.
+    /// Returns an error if the current thread already holds the lock.
+    fn acquire() -> Result<Self, PushrodError> {
+        if HOLDS_HEADLESS_LOCK.with(|x| x.get()) {
+            return Err(PushrodError::Sdl(String::from(
+                "[HeadlessEngine::new] A HeadlessEngine already exists on this thread.",
+            )));
+        }
+
+        // A test that panics while holding the lock poisons it, but leaves nothing to recover.
+        let guard = HEADLESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        HOLDS_HEADLESS_LOCK.with(|x| x.set(true));
+
+        Ok(Self { _guard: guard })
+    }
+}
+
+impl Drop for HeadlessLock {
+    fn drop(&mut self) {
+        HOLDS_HEADLESS_LOCK.with(|x| x.set(false));
+    }
+}
+
+/// Contains the `Engine` being driven, along with the offscreen `Canvas` it draws against, and
+/// the `SDL2` handles that must be kept alive while the canvas is in use.  Fields are dropped in
+/// order, so the lock is released last.
+pub struct HeadlessEngine {
+    engine: Engine,
+    canvas: Canvas<Window>,
+    event_pump: EventPump,
+    _video: VideoSubsystem,
+    _sdl: Sdl,
+    _lock: HeadlessLock,
+}
+
+impl HeadlessEngine {
+    /// Creates a new `HeadlessEngine` that drives the specified `Engine`.  The offscreen canvas is
+    /// created using the `Size` of the `Engine`.  Waits until any `HeadlessEngine` on another
+    /// thread has been dropped.  Returns an error if `SDL2` could not be initialized using the
+    /// `offscreen` video driver, or a `HeadlessEngine` already exists on this thread.
+    pub fn new(engine: Engine) -> Result<Self, PushrodError> {
+        let lock = HeadlessLock::acquire()?;
+
+        sdl2::hint::set("SDL_VIDEODRIVER", "offscreen");
+
+        let sdl = sdl2::init().map_err(PushrodError::Sdl)?;
+        let video = sdl.video().map_err(PushrodError::Sdl)?;
+        let size = engine.get_size();
+        let window = video
+            .window("pushrod-headless", size.w, size.h)
+            .hidden()
+            .build()?;
+
+        // Uses the software renderer, as no GPU is assumed to be available.  Texture targets
+        // are still required, as `Widget`s draw against their own textures.
+        let mut canvas = window
+            .into_canvas()
+            .software()
+            .target_texture()
+            .build()?;
+
+        canvas.set_draw_color(Color::RGBA(255, 255, 255, 255));
+        canvas.clear();
+        canvas.present();
+
+        let event_pump = sdl.event_pump().map_err(PushrodError::Sdl)?;
+        let mut engine = engine;
+
+        engine.set_waker(&sdl);
+
+        Ok(Self {
+            engine,
+            canvas,
+            event_pump,
+            _video: video,
+            _sdl: sdl,
+            _lock: lock,
+        })
+    }
+
+    /// Runs a single iteration of the run loop: pending `SDL2` events are processed, followed by
+    /// any messages posted through an `EngineSender`, and any timers that are due.  Invalidated
+    /// `Widget`s are then drawn to the offscreen canvas, reporting any error that occurs while
+    /// drawing.  Returns `false` if the `Engine` has been shut down, or a quit event was received,
+    /// `true` otherwise.
+    pub fn step(&mut self) -> bool {
+        for event in self.event_pump.poll_iter() {
+            if !self.engine.handle_event(event) {
+                self.engine.shutdown();
+            }
+        }
+
+        self.engine.process_messages();
+        self.engine.process_timers();
+
+        // Injected resize events change the size of the `Engine`, so the offscreen window is
+        // resized to match before drawing.
+        let size = self.engine.get_size();
+
+        if self.canvas.window().size() != (size.w, size.h) {
+            if let Err(e) = self.canvas.window_mut().set_size(size.w, size.h) {
+                warn!(target: "pushrod::layout", "Unable to resize offscreen window: {}", e);
+            }
+        }
+
+        match self.engine.draw(&mut self.canvas) {
+            Ok(true) => self.canvas.present(),
+            Ok(false) => {}
+            Err(e) => error!(target: "pushrod::draw", "Unable to draw: {}", e),
+        }
+
+        self.engine.is_running()
+    }
+
+    /// Injects an `SDL2` `Event` into the `Engine`, as if it had been received from the event
+    /// pump.  The event is handled immediately, but is not drawn until the next `step`.
+    pub fn inject_event(&mut self, event: Event) -> bool {
+        self.engine.inject_event(event)
+    }
+
+    /// Replays every remaining event from an `EventPlayer`, running a single `step` after each
+    /// event is injected.  Events are replayed in order, regardless of the time at which they were
+    /// recorded, so that the results are identical between runs.  Returns `false` if the `Engine`
+    /// was shut down during the replay, `true` otherwise.
+    pub fn replay(&mut self, player: &mut EventPlayer) -> bool {
+        while let Some(event) = player.next_event() {
+            if !self.engine.inject_event(event) {
+                self.engine.shutdown();
+            }
+
+            if !self.step() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Returns the `Engine` being driven, so that `Widget`s and event handlers can be added.
+    pub fn get_engine(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+
+    /// Returns the offscreen `Canvas` that the `Engine` draws against.
+    pub fn get_canvas(&mut self) -> &mut Canvas<Window> {
+        &mut self.canvas
+    }
+
+    /// Returns both the offscreen `Canvas` and the `FontCache` of the `Engine`, so that a
+    /// `Widget` can be drawn outside of the draw loop.
+    pub(crate) fn get_canvas_and_font_cache(&mut self) -> (&mut Canvas<Window>, &mut FontCache) {
+        (&mut self.canvas, self.engine.get_font_cache())
+    }
+
+    /// Reads back the contents of the offscreen canvas, returning the pixels as a list of
+    /// `RGBA` bytes, from left to right, top to bottom.
+    pub fn read_pixels(&self) -> Result<Vec<u8>, PushrodError> {
+        self.canvas
+            .read_pixels(None, PixelFormatEnum::RGBA32)
+            .map_err(PushrodError::Render)
+    }
+}
@@ -22,22 +22,48 @@
 //!
 //! # Dependencies
 //!
-//! Pushrod uses the following dependency:
+//! Pushrod uses the following dependencies:
 //! ```ignore
+//! [dependencies.log]
+//! version = "^0.4.17"
+//!
 //! [dependencies.sdl2]
 //! version = "^0.36.0"
 //! features = ["ttf", "image", "unsafe_textures"]
 //! ```
 //!
 //! Note, the `unsafe_textures` feature is required for OpenGL functionality.
+//!
+//! # Logging
+//!
+//! Pushrod emits its diagnostics through the `log` facade, so nothing is printed unless the
+//! application installs a logger, such as `env_logger`.  Each message is logged against one of the
+//! following targets, so that each area can be filtered separately:
+//!
+//! - `pushrod::texture`: creation of the textures that `Widget`s draw against.
+//! - `pushrod::event`: events sent to `Widget`s, focus changes, clicks, and event recording.
+//! - `pushrod::layout`: moving and resizing `Widget`s, and resizing windows.
+//! - `pushrod::draw`: `Widget`s that fail to draw, and composition of the scene.
+//!
+//! Failures are logged at the `error` and `warn` levels.  Routine activity is logged at the
+//! `debug` level, and per-event detail at the `trace` level.  Messages about a single `Widget`
+//! include its `WidgetId`.  For example, `RUST_LOG=pushrod=warn,pushrod::draw=debug` shows only
+//! failures, along with the activity of the draw loop.
 
+pub mod application;
 pub mod base_widget;
 pub mod box_widget;
 pub mod cache;
+pub mod channel;
 pub mod engine;
+pub mod error;
 pub mod event;
 pub mod geometry;
+pub mod headless;
+pub mod recorder;
+pub mod snapshot;
 pub mod texture;
+pub mod timer;
 pub mod widget;
 pub mod font;
 pub mod text_widget;
@@ -0,0 +1,731 @@
+// Event Recorder
+// Pushrod
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # EventRecorder and EventPlayer
+//!
+//! The `EventRecorder` captures the `SDL2` `Event`s of a running session, along with the time
+//! at which they were received, and writes them to a file.  The `EventPlayer` loads a recorded
+//! session, and replays its events, either in real time against a running `Engine`, or
+//! deterministically one event at a time against a `HeadlessEngine`.
+//!
+//! Recordings are stored as text, one event per line, in the form:
+//!
+//! ```text
+//! <elapsed milliseconds> <event name> <event fields ...>
+//! ```
+//!
+//! The following events are recorded: `MouseMotion`, `MouseButtonDown`, `MouseButtonUp`,
+//! `MouseWheel`, `KeyDown`, `KeyUp`, `TextInput`, `TextEditing`, `Window`, and `Quit`.  Window
+//! events are recorded when a window is shown, hidden, exposed, moved, resized, minimized,
+//! maximized, restored, gains or loses focus, or is closed.  All other events are ignored by the
+//! recorder.
+
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use sdl2::mouse::{MouseButton, MouseState, MouseWheelDirection};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// An `Event` that was recorded, along with the number of milliseconds since the start of the
+/// recording at which it was received.
+#[derive(Debug, Clone)]
+pub struct RecordedEvent {
+    elapsed_ms: u32,
+    event: Event,
+}
+
+impl RecordedEvent {
+    /// Creates a new `RecordedEvent`.
+    pub fn new(elapsed_ms: u32, event: Event) -> Self {
+        Self { elapsed_ms, event }
+    }
+
+    /// Returns the number of milliseconds since the start of the recording at which the `Event`
+    /// was received.
+    pub fn get_elapsed_ms(&self) -> u32 {
+        self.elapsed_ms
+    }
+
+    /// Returns the recorded `Event`.
+    pub fn get_event(&self) -> &Event {
+        &self.event
+    }
+
+    /// Encodes the `RecordedEvent` as a single line of text, without a trailing newline.  Returns
+    /// `None` if the `Event` is not one that can be recorded.
+    pub fn encode(&self) -> Option<String> {
+        let fields = match &self.event {
+            Event::MouseMotion {
+                window_id,
+                which,
+                mousestate,
+                x,
+                y,
+                xrel,
+                yrel,
+                ..
+            } => format!(
+                "MouseMotion {} {} {} {} {} {} {}",
+                window_id,
+                which,
+                mousestate.to_sdl_state(),
+                x,
+                y,
+                xrel,
+                yrel
+            ),
+
+            Event::MouseButtonDown {
+                window_id,
+                which,
+                mouse_btn,
+                clicks,
+                x,
+                y,
+                ..
+            } => format!(
+                "MouseButtonDown {} {} {} {} {} {}",
+                window_id, which, *mouse_btn as u8, clicks, x, y
+            ),
+
+            Event::MouseButtonUp {
+                window_id,
+                which,
+                mouse_btn,
+                clicks,
+                x,
+                y,
+                ..
+            } => format!(
+                "MouseButtonUp {} {} {} {} {} {}",
+                window_id, which, *mouse_btn as u8, clicks, x, y
+            ),
+
+            Event::MouseWheel {
+                window_id,
+                which,
+                x,
+                y,
+                direction,
+                precise_x,
+                precise_y,
+                ..
+            } => format!(
+                "MouseWheel {} {} {} {} {} {} {}",
+                window_id,
+                which,
+                x,
+                y,
+                direction.to_ll(),
+                precise_x,
+                precise_y
+            ),
+
+            Event::KeyDown {
+                window_id,
+                keycode,
+                scancode,
+                keymod,
+                repeat,
+                ..
+            } => format!(
+                "KeyDown {} {} {} {} {}",
+                window_id,
+                keycode.map_or(-1, |x| x as i32),
+                scancode.map_or(-1, |x| x as i32),
+                keymod.bits(),
+                repeat
+            ),
+
+            Event::KeyUp {
+                window_id,
+                keycode,
+                scancode,
+                keymod,
+                repeat,
+                ..
+            } => format!(
+                "KeyUp {} {} {} {} {}",
+                window_id,
+                keycode.map_or(-1, |x| x as i32),
+                scancode.map_or(-1, |x| x as i32),
+                keymod.bits(),
+                repeat
+            ),
+
+            Event::TextInput {
+                window_id, text, ..
+            } => format!("TextInput {} {}", window_id, escape(text)),
+
+            Event::TextEditing {
+                window_id,
+                text,
+                start,
+                length,
+                ..
+            } => format!(
+                "TextEditing {} {} {} {}",
+                window_id,
+                start,
+                length,
+                escape(text)
+            ),
+
+            Event::Window {
+                window_id,
+                win_event,
+                ..
+            } => format!("Window {} {}", window_id, encode_window_event(win_event)?),
+
+            Event::Quit { .. } => String::from("Quit"),
+
+            _unsupported => return None,
+        };
+
+        Some(format!("{} {}", self.elapsed_ms, fields))
+    }
+
+    /// Decodes a `RecordedEvent` from a single line of text produced by `encode`.  The
+    /// `timestamp` of the decoded `Event` is set to the recorded elapsed time, so that replayed
+    /// events are identical between runs.
+    pub fn decode(line: &str) -> Result<Self, Error> {
+        let invalid = || Error::new(ErrorKind::InvalidData, format!("Invalid event: {}", line));
+        let mut parts = line.splitn(3, ' ');
+        let timestamp: u32 = parts
+            .next()
+            .and_then(|x| x.parse().ok())
+            .ok_or_else(invalid)?;
+        let name = parts.next().ok_or_else(invalid)?;
+        let remainder = parts.next().unwrap_or("");
+        let fields: Vec<&str> = remainder.split(' ').collect();
+
+        // Parses the numbered field into the requested type.
+        macro_rules! field {
+            ($index:expr) => {
+                fields
+                    .get($index)
+                    .and_then(|x| x.parse().ok())
+                    .ok_or_else(invalid)?
+            };
+        }
+
+        let event = match name {
+            "MouseMotion" => Event::MouseMotion {
+                timestamp,
+                window_id: field!(0),
+                which: field!(1),
+                mousestate: MouseState::from_sdl_state(field!(2)),
+                x: field!(3),
+                y: field!(4),
+                xrel: field!(5),
+                yrel: field!(6),
+            },
+
+            "MouseButtonDown" => Event::MouseButtonDown {
+                timestamp,
+                window_id: field!(0),
+                which: field!(1),
+                mouse_btn: MouseButton::from_ll(field!(2)),
+                clicks: field!(3),
+                x: field!(4),
+                y: field!(5),
+            },
+
+            "MouseButtonUp" => Event::MouseButtonUp {
+                timestamp,
+                window_id: field!(0),
+                which: field!(1),
+                mouse_btn: MouseButton::from_ll(field!(2)),
+                clicks: field!(3),
+                x: field!(4),
+                y: field!(5),
+            },
+
+            "MouseWheel" => Event::MouseWheel {
+                timestamp,
+                window_id: field!(0),
+                which: field!(1),
+                x: field!(2),
+                y: field!(3),
+                direction: MouseWheelDirection::from_ll(field!(4)),
+                precise_x: field!(5),
+                precise_y: field!(6),
+            },
+
+            "KeyDown" => Event::KeyDown {
+                timestamp,
+                window_id: field!(0),
+                keycode: Keycode::from_i32(field!(1)),
+                scancode: Scancode::from_i32(field!(2)),
+                keymod: Mod::from_bits_truncate(field!(3)),
+                repeat: field!(4),
+            },
+
+            "KeyUp" => Event::KeyUp {
+                timestamp,
+                window_id: field!(0),
+                keycode: Keycode::from_i32(field!(1)),
+                scancode: Scancode::from_i32(field!(2)),
+                keymod: Mod::from_bits_truncate(field!(3)),
+                repeat: field!(4),
+            },
+
+            "TextInput" => {
+                let (window_id, text) = remainder.split_once(' ').ok_or_else(invalid)?;
+
+                Event::TextInput {
+                    timestamp,
+                    window_id: window_id.parse().map_err(|_| invalid())?,
+                    text: unescape(text),
+                }
+            }
+
+            "TextEditing" => {
+                let mut parts = remainder.splitn(4, ' ');
+                let mut next_number = || {
+                    parts
+                        .next()
+                        .and_then(|x| x.parse::<i64>().ok())
+                        .ok_or_else(invalid)
+                };
+                let window_id = next_number()? as u32;
+                let start = next_number()? as i32;
+                let length = next_number()? as i32;
+
+                Event::TextEditing {
+                    timestamp,
+                    window_id,
+                    text: unescape(parts.next().unwrap_or("")),
+                    start,
+                    length,
+                }
+            }
+
+            "Window" => Event::Window {
+                timestamp,
+                window_id: field!(0),
+                win_event: decode_window_event(&fields[1..]).ok_or_else(invalid)?,
+            },
+
+            "Quit" => Event::Quit { timestamp },
+
+            _unknown => return Err(invalid()),
+        };
+
+        Ok(Self::new(timestamp, event))
+    }
+}
+
+/// Encodes a `WindowEvent` as its name, followed by its data for events that carry any.  Returns
+/// `None` if the `WindowEvent` is not one that can be recorded.
+fn encode_window_event(win_event: &WindowEvent) -> Option<String> {
+    let encoded = match win_event {
+        WindowEvent::Shown => String::from("Shown"),
+        WindowEvent::Hidden => String::from("Hidden"),
+        WindowEvent::Exposed => String::from("Exposed"),
+        WindowEvent::Moved(x, y) => format!("Moved {} {}", x, y),
+        WindowEvent::Resized(w, h) => format!("Resized {} {}", w, h),
+        WindowEvent::SizeChanged(w, h) => format!("SizeChanged {} {}", w, h),
+        WindowEvent::Minimized => String::from("Minimized"),
+        WindowEvent::Maximized => String::from("Maximized"),
+        WindowEvent::Restored => String::from("Restored"),
+        WindowEvent::FocusGained => String::from("FocusGained"),
+        WindowEvent::FocusLost => String::from("FocusLost"),
+        WindowEvent::Close => String::from("Close"),
+        _unsupported => return None,
+    };
+
+    Some(encoded)
+}
+
+/// Decodes a `WindowEvent` from the fields produced by `encode_window_event`.  Returns `None` if
+/// the fields are not valid.
+fn decode_window_event(fields: &[&str]) -> Option<WindowEvent> {
+    let data = |index: usize| fields.get(index).and_then(|x| x.parse::<i32>().ok());
+
+    let win_event = match *fields.first()? {
+        "Shown" => WindowEvent::Shown,
+        "Hidden" => WindowEvent::Hidden,
+        "Exposed" => WindowEvent::Exposed,
+        "Moved" => WindowEvent::Moved(data(1)?, data(2)?),
+        "Resized" => WindowEvent::Resized(data(1)?, data(2)?),
+        "SizeChanged" => WindowEvent::SizeChanged(data(1)?, data(2)?),
+        "Minimized" => WindowEvent::Minimized,
+        "Maximized" => WindowEvent::Maximized,
+        "Restored" => WindowEvent::Restored,
+        "FocusGained" => WindowEvent::FocusGained,
+        "FocusLost" => WindowEvent::FocusLost,
+        "Close" => WindowEvent::Close,
+        _unknown => return None,
+    };
+
+    Some(win_event)
+}
+
+/// Escapes backslashes and line breaks in recorded text, so that each event occupies exactly one
+/// line.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Reverses the escaping performed by `escape`.
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// Records `Event`s to a writer, timestamped relative to the creation of the recorder.
+pub struct EventRecorder {
+    writer: Box<dyn Write>,
+    start: Instant,
+}
+
+impl EventRecorder {
+    /// Creates a new `EventRecorder` that writes to the specified writer.
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self {
+            writer,
+            start: Instant::now(),
+        }
+    }
+
+    /// Creates a new `EventRecorder` that writes to a newly created file at `path`.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Self::new(Box::new(BufWriter::new(File::create(path)?))))
+    }
+
+    /// Records an `Event`, timestamped with the time elapsed since the recorder was created.
+    /// Events that cannot be recorded are silently ignored.
+    pub fn record(&mut self, event: &Event) -> Result<(), Error> {
+        let elapsed_ms = self.start.elapsed().as_millis() as u32;
+
+        if let Some(line) = RecordedEvent::new(elapsed_ms, event.clone()).encode() {
+            writeln!(self.writer, "{}", line)?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes any recorded events to the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()
+    }
+}
+
+/// Replays a list of `RecordedEvent`s in the order in which they were recorded.
+pub struct EventPlayer {
+    events: Vec<RecordedEvent>,
+    position: usize,
+}
+
+impl EventPlayer {
+    /// Creates a new `EventPlayer` that replays the specified events.
+    pub fn new(events: Vec<RecordedEvent>) -> Self {
+        Self {
+            events,
+            position: 0,
+        }
+    }
+
+    /// Loads a recording from the file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a recording from a reader.  Blank lines are ignored.
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, Error> {
+        let mut events = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+
+            if !line.trim().is_empty() {
+                events.push(RecordedEvent::decode(&line)?);
+            }
+        }
+
+        Ok(Self::new(events))
+    }
+
+    /// Returns all of the `RecordedEvent`s in the recording.
+    pub fn get_events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Returns `true` once every event has been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.events.len()
+    }
+
+    /// Restarts the replay from the first event.
+    pub fn rewind(&mut self) {
+        self.position = 0;
+    }
+
+    /// Returns the next `Event` to replay, regardless of the time at which it was recorded.
+    /// Returns `None` once every event has been replayed.
+    pub fn next_event(&mut self) -> Option<Event> {
+        let event = self.events.get(self.position)?.event.clone();
+
+        self.position += 1;
+
+        Some(event)
+    }
+
+    /// Returns the time at which the next event to replay was recorded, relative to the start of
+    /// the recording.  Returns `None` once every event has been replayed.
+    pub fn get_next_due(&self) -> Option<Duration> {
+        self.events
+            .get(self.position)
+            .map(|x| Duration::from_millis(x.elapsed_ms as u64))
+    }
+
+    /// Returns the `Event`s that were recorded at or before the `elapsed` time, which have not
+    /// yet been replayed.  This is used to replay a recording in real time.
+    pub fn poll_due(&mut self, elapsed: Duration) -> Vec<Event> {
+        let elapsed_ms = elapsed.as_millis();
+        let mut due = Vec::new();
+
+        while let Some(recorded) = self.events.get(self.position) {
+            if recorded.elapsed_ms as u128 > elapsed_ms {
+                break;
+            }
+
+            due.push(recorded.event.clone());
+            self.position += 1;
+        }
+
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes an `Event` recorded at `elapsed_ms`, decodes it, and checks that the decoded
+    /// `Event` is identical.  The `timestamp` of the `Event` must equal `elapsed_ms`.
+    fn assert_round_trip(elapsed_ms: u32, event: Event) {
+        let line = RecordedEvent::new(elapsed_ms, event.clone())
+            .encode()
+            .expect("Event should be recordable");
+
+        assert!(!line.contains('\n'), "Encoded event spans more than one line: {:?}", line);
+
+        let decoded = RecordedEvent::decode(&line).unwrap();
+
+        assert_eq!(decoded.get_elapsed_ms(), elapsed_ms);
+        assert_eq!(decoded.get_event(), &event);
+    }
+
+    #[test]
+    fn test_round_trip_mouse_events() {
+        assert_round_trip(
+            10,
+            Event::MouseMotion {
+                timestamp: 10,
+                window_id: 1,
+                which: 0,
+                mousestate: MouseState::from_sdl_state(1),
+                x: 20,
+                y: -5,
+                xrel: 3,
+                yrel: -4,
+            },
+        );
+        assert_round_trip(
+            11,
+            Event::MouseButtonDown {
+                timestamp: 11,
+                window_id: 1,
+                which: 0,
+                mouse_btn: MouseButton::Left,
+                clicks: 2,
+                x: 20,
+                y: 30,
+            },
+        );
+        assert_round_trip(
+            12,
+            Event::MouseButtonUp {
+                timestamp: 12,
+                window_id: 1,
+                which: 0,
+                mouse_btn: MouseButton::Right,
+                clicks: 1,
+                x: 20,
+                y: 30,
+            },
+        );
+        assert_round_trip(
+            13,
+            Event::MouseWheel {
+                timestamp: 13,
+                window_id: 1,
+                which: 0,
+                x: 0,
+                y: -1,
+                direction: MouseWheelDirection::Flipped,
+                precise_x: 0.0,
+                precise_y: -1.5,
+            },
+        );
+    }
+
+    #[test]
+    fn test_round_trip_key_events() {
+        assert_round_trip(
+            20,
+            Event::KeyDown {
+                timestamp: 20,
+                window_id: 1,
+                keycode: Some(Keycode::Tab),
+                scancode: Some(Scancode::Tab),
+                keymod: Mod::LSHIFTMOD,
+                repeat: false,
+            },
+        );
+        assert_round_trip(
+            21,
+            Event::KeyUp {
+                timestamp: 21,
+                window_id: 1,
+                keycode: None,
+                scancode: None,
+                keymod: Mod::NOMOD,
+                repeat: true,
+            },
+        );
+    }
+
+    #[test]
+    fn test_round_trip_text_events() {
+        for text in ["", "plain", "two words", " padded ", "back\\slash", "line\nbreak\r\n", "\\n"] {
+            assert_round_trip(
+                30,
+                Event::TextInput {
+                    timestamp: 30,
+                    window_id: 1,
+                    text: text.to_string(),
+                },
+            );
+            assert_round_trip(
+                31,
+                Event::TextEditing {
+                    timestamp: 31,
+                    window_id: 1,
+                    text: text.to_string(),
+                    start: 2,
+                    length: -1,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip_window_events() {
+        for win_event in [
+            WindowEvent::Shown,
+            WindowEvent::Hidden,
+            WindowEvent::Exposed,
+            WindowEvent::Moved(-10, 20),
+            WindowEvent::Resized(640, 480),
+            WindowEvent::SizeChanged(0, 480),
+            WindowEvent::Minimized,
+            WindowEvent::Maximized,
+            WindowEvent::Restored,
+            WindowEvent::FocusGained,
+            WindowEvent::FocusLost,
+            WindowEvent::Close,
+        ] {
+            assert_round_trip(
+                40,
+                Event::Window {
+                    timestamp: 40,
+                    window_id: 2,
+                    win_event,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip_quit() {
+        assert_round_trip(50, Event::Quit { timestamp: 50 });
+    }
+
+    #[test]
+    fn test_unsupported_events_are_not_encoded() {
+        let event = Event::Window {
+            timestamp: 0,
+            window_id: 1,
+            win_event: WindowEvent::Enter,
+        };
+
+        assert!(RecordedEvent::new(0, event).encode().is_none());
+        assert!(RecordedEvent::new(0, Event::AppTerminating { timestamp: 0 }).encode().is_none());
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        for line in ["", "abc Quit", "10", "10 Unknown", "10 KeyDown 1 x", "10 Window 1 Moved 5", "10 Window 1 Enter"] {
+            assert!(RecordedEvent::decode(line).is_err(), "Decoded invalid line: {:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_escape_unescape() {
+        for text in ["", "a b", "\\", "\\\\n", "x\ny\rz", "trailing\\"] {
+            assert_eq!(unescape(&escape(text)), text);
+            assert!(!escape(text).contains('\n'));
+        }
+    }
+
+    #[test]
+    fn test_player_reads_recording() {
+        let recording = "0 Quit\n\n5 TextInput 1 hello world\n";
+        let mut player = EventPlayer::read(recording.as_bytes()).unwrap();
+
+        assert_eq!(player.next_event(), Some(Event::Quit { timestamp: 0 }));
+        assert_eq!(
+            player.next_event(),
+            Some(Event::TextInput {
+                timestamp: 5,
+                window_id: 1,
+                text: String::from("hello world"),
+            })
+        );
+        assert_eq!(player.next_event(), None);
+    }
+}
@@ -0,0 +1,500 @@
+// Snapshot Testing
+// Pushrod
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Snapshot
+//!
+//! Test support for rendering `Widget`s to pixels, and comparing them against golden `PNG`
+//! images stored on disk.
+//!
+//! A `Snapshot` is a rendered image, stored as a list of `RGBA` bytes.  Snapshots are created
+//! from a single `Widget`, or the entire `WidgetCache` of an `Engine`, using a
+//! `HeadlessEngine`, so no display is required.
+//!
+//! The `GoldenTester` compares a `Snapshot` against a golden image with a configurable per-pixel
+//! tolerance.  If the golden image does not exist, it is created.  If the images differ, the
+//! rendered image is written next to the golden image with a `.actual.png` extension, along with
+//! a diff image with a `.diff.png` extension, in which the mismatched pixels are drawn in red.
+//!
+//! ```rust,no_run
+//! # use pushrod::box_widget::BoxWidget;
+//! # use pushrod::engine::Engine;
+//! # use pushrod::geometry::{point, size};
+//! # use pushrod::headless::HeadlessEngine;
+//! # use pushrod::snapshot::{GoldenTester, Snapshot};
+//! # use sdl2::pixels::Color;
+//! let mut headless = HeadlessEngine::new(Engine::new(size(200, 200), 60)).unwrap();
+//! let mut box_widget = BoxWidget::new(point(0, 0), size(100, 100), Color::BLUE, 3);
+//! let snapshot = Snapshot::from_widget(&mut headless, &mut box_widget).unwrap();
+//!
+//! GoldenTester::new("tests/golden")
+//!     .assert_matches("box_widget", &snapshot)
+//!     .unwrap();
+//! ```
+//!
+//! Golden images can be regenerated by setting the `PUSHROD_UPDATE_GOLDEN` environment variable
+//! to `1` when running tests.
+
+use crate::error::PushrodError;
+use crate::font::FontCache;
+use crate::geometry::{origin_point, make_rect, Size};
+use crate::headless::HeadlessEngine;
+use crate::widget::Widget;
+use sdl2::image::{LoadSurface, SaveSurface};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::render::{Canvas, Texture};
+use sdl2::surface::Surface;
+use sdl2::video::Window;
+use std::path::{Path, PathBuf};
+
+/// Number of bytes in a single `RGBA` pixel.
+const BYTES_PER_PIXEL: usize = 4;
+
+/// A rendered image, containing its `Size`, and its pixels as a list of `RGBA` bytes.
+#[derive(Clone)]
+pub struct Snapshot {
+    size: Size,
+    pixels: Vec<u8>,
+}
+
+impl Snapshot {
+    /// Creates a new `Snapshot` from a `Size` and a list of `RGBA` bytes.  Returns a
+    /// `SnapshotSize` error if the list of pixels does not contain exactly `w * h * 4` bytes.
+    pub fn new(size: Size, pixels: Vec<u8>) -> Result<Self, PushrodError> {
+        if pixels.len() != size.w as usize * size.h as usize * BYTES_PER_PIXEL {
+            return Err(PushrodError::SnapshotSize {
+                size,
+                len: pixels.len(),
+            });
+        }
+
+        Ok(Self { size, pixels })
+    }
+
+    /// Renders the entire `WidgetCache` of the `Engine` driven by the `HeadlessEngine`.  All
+    /// `Widget`s are invalidated first, so that the snapshot contains the full window contents.
+    pub fn from_engine(headless: &mut HeadlessEngine) -> Result<Self, PushrodError> {
+        let size = headless.get_engine().get_size();
+
+        headless.get_engine().get_widget_cache().invalidate_all();
+        headless.step();
+
+        Self::new(size, headless.read_pixels()?)
+    }
+
+    /// Renders a single `Widget` at its own `Size`, using the canvas and fonts of the
+    /// `HeadlessEngine`.  The `Widget` is drawn over a white background, in the same way that
+    /// it would be drawn by the `Engine`.
+    pub fn from_widget(headless: &mut HeadlessEngine, widget: &mut dyn Widget) -> Result<Self, PushrodError> {
+        let size = *widget.get_size();
+        let (canvas, font_cache) = headless.get_canvas_and_font_cache();
+        let mut target = canvas
+            .create_texture_target(PixelFormatEnum::RGBA32, size.w, size.h)
+            .map_err(|e| PushrodError::texture(size, e))?;
+
+        widget.set_invalidated(true);
+
+        let pixels = Self::read_widget(canvas, font_cache, widget, &mut target, size);
+
+        // Textures are not destroyed when dropped, as `unsafe_textures` is enabled.
+        unsafe {
+            target.destroy();
+        }
+
+        widget.set_invalidated(false);
+
+        Self::new(size, pixels?)
+    }
+
+    /// Internal function that draws a `Widget`, copies it over a white background in the `target`
+    /// texture, and reads back the pixels of the `target`.
+    fn read_widget(
+        canvas: &mut Canvas<Window>,
+        font_cache: &mut FontCache,
+        widget: &mut dyn Widget,
+        target: &mut Texture,
+        size: Size,
+    ) -> Result<Vec<u8>, PushrodError> {
+        let texture = widget
+            .draw(canvas, font_cache)?
+            .ok_or_else(|| PushrodError::Render(String::from("[Snapshot::from_widget] No texture presented.")))?;
+        let mut result = Ok(Vec::new());
+
+        canvas.with_texture_canvas(target, |c| {
+            c.set_draw_color(Color::RGBA(255, 255, 255, 255));
+            c.clear();
+
+            result = c
+                .copy(texture, None, make_rect(origin_point(), size))
+                .and_then(|_| c.read_pixels(None, PixelFormatEnum::RGBA32));
+        })?;
+
+        result.map_err(PushrodError::Render)
+    }
+
+    /// Loads a `Snapshot` from a `PNG` image on disk.
+    pub fn load_png<P: AsRef<Path>>(path: P) -> Result<Self, PushrodError> {
+        let surface = Surface::from_file(path)
+            .and_then(|x| x.convert_format(PixelFormatEnum::RGBA32))
+            .map_err(PushrodError::Sdl)?;
+        let size = Size::new(surface.width(), surface.height());
+        let pitch = surface.pitch() as usize;
+        let row_length = size.w as usize * BYTES_PER_PIXEL;
+        let mut pixels = Vec::with_capacity(row_length * size.h as usize);
+
+        // Surfaces may pad each row of pixels, so the rows are copied individually.
+        surface.with_lock(|data| {
+            for row in 0..size.h as usize {
+                pixels.extend_from_slice(&data[row * pitch..row * pitch + row_length]);
+            }
+        });
+
+        Self::new(size, pixels)
+    }
+
+    /// Saves the `Snapshot` as a `PNG` image on disk.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), PushrodError> {
+        let mut pixels = self.pixels.clone();
+        let pitch = self.size.w * BYTES_PER_PIXEL as u32;
+
+        let surface = Surface::from_data(
+            &mut pixels,
+            self.size.w,
+            self.size.h,
+            pitch,
+            PixelFormatEnum::RGBA32,
+        )
+        .map_err(PushrodError::Sdl)?;
+
+        surface.save(path).map_err(PushrodError::Sdl)
+    }
+
+    /// Returns the `Size` of the `Snapshot`.
+    pub fn get_size(&self) -> Size {
+        self.size
+    }
+
+    /// Returns the pixels of the `Snapshot` as a list of `RGBA` bytes.
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Returns the `Color` of the pixel at the `x` and `y` coordinates specified.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Color {
+        let offset = (y as usize * self.size.w as usize + x as usize) * BYTES_PER_PIXEL;
+
+        Color::RGBA(
+            self.pixels[offset],
+            self.pixels[offset + 1],
+            self.pixels[offset + 2],
+            self.pixels[offset + 3],
+        )
+    }
+
+    /// Compares this `Snapshot` against an `expected` `Snapshot`.  Pixels match if each of their
+    /// `RGBA` components differ by no more than `tolerance`.  Returns a `SnapshotDiff` describing
+    /// the result.  If the sizes of the snapshots differ, the snapshots never match: every pixel
+    /// of the larger snapshot is considered a mismatch, and no diff image is generated.
+    pub fn compare(&self, expected: &Snapshot, tolerance: u8) -> SnapshotDiff {
+        if self.size != expected.size {
+            return SnapshotDiff {
+                mismatched: (self.size.w * self.size.h).max(expected.size.w * expected.size.h),
+                size_mismatch: true,
+                diff: None,
+            };
+        }
+
+        let mut mismatched = 0;
+        let mut diff_pixels = Vec::with_capacity(self.pixels.len());
+
+        for (actual, expected) in self
+            .pixels
+            .chunks_exact(BYTES_PER_PIXEL)
+            .zip(expected.pixels.chunks_exact(BYTES_PER_PIXEL))
+        {
+            let matches = actual
+                .iter()
+                .zip(expected.iter())
+                .all(|(a, e)| a.abs_diff(*e) <= tolerance);
+
+            if matches {
+                // Matching pixels are drawn as a faded copy of the expected image, so that
+                // mismatches can be located in context.
+                let gray = ((expected[0] as u32 + expected[1] as u32 + expected[2] as u32) / 3) as u8;
+                let faded = 192 + gray / 4;
+
+                diff_pixels.extend_from_slice(&[faded, faded, faded, 255]);
+            } else {
+                mismatched += 1;
+                diff_pixels.extend_from_slice(&[255, 0, 0, 255]);
+            }
+        }
+
+        SnapshotDiff {
+            mismatched,
+            size_mismatch: false,
+            diff: Some(Snapshot {
+                size: self.size,
+                pixels: diff_pixels,
+            }),
+        }
+    }
+}
+
+/// The result of comparing two `Snapshot`s: the number of mismatched pixels, whether the sizes of
+/// the snapshots differed, and an optional diff image.
+pub struct SnapshotDiff {
+    mismatched: u32,
+    size_mismatch: bool,
+    diff: Option<Snapshot>,
+}
+
+impl SnapshotDiff {
+    /// Returns `true` if the snapshots were the same size, and no pixels were mismatched.
+    pub fn is_match(&self) -> bool {
+        !self.size_mismatch && self.mismatched == 0
+    }
+
+    /// Returns `true` if the compared snapshots were different sizes.
+    pub fn is_size_mismatch(&self) -> bool {
+        self.size_mismatch
+    }
+
+    /// Returns the number of mismatched pixels.
+    pub fn get_mismatched(&self) -> u32 {
+        self.mismatched
+    }
+
+    /// Returns the diff image, in which mismatched pixels are drawn in red.  `None` if the
+    /// compared snapshots were different sizes.
+    pub fn get_diff(&self) -> Option<&Snapshot> {
+        self.diff.as_ref()
+    }
+}
+
+/// Compares `Snapshot`s against golden `PNG` images stored in a directory.
+pub struct GoldenTester {
+    directory: PathBuf,
+    tolerance: u8,
+    update: bool,
+}
+
+impl GoldenTester {
+    /// Creates a new `GoldenTester` that stores its golden images in `directory`.  The default
+    /// tolerance is `0`, meaning that pixels must match exactly.  Golden images are rewritten
+    /// instead of compared if the `PUSHROD_UPDATE_GOLDEN` environment variable is set to `1`.
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            tolerance: 0,
+            update: std::env::var("PUSHROD_UPDATE_GOLDEN").is_ok_and(|x| x == "1"),
+        }
+    }
+
+    /// Sets the maximum difference allowed for each `RGBA` component of a pixel before it is
+    /// considered a mismatch.
+    pub fn set_tolerance(&mut self, tolerance: u8) -> &mut Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets whether or not golden images are rewritten with the `Snapshot` being compared,
+    /// instead of being compared against.
+    pub fn set_update(&mut self, update: bool) -> &mut Self {
+        self.update = update;
+        self
+    }
+
+    /// Compares a `Snapshot` against the golden image called `name`.  If the golden image does
+    /// not exist, or golden images are being updated, the `Snapshot` is saved as the golden image.
+    ///
+    /// Returns a `SnapshotMismatch` error if the images differ, after writing the `Snapshot` and a diff
+    /// image alongside the golden image.
+    pub fn assert_matches(&self, name: &str, snapshot: &Snapshot) -> Result<(), PushrodError> {
+        let golden_path = self.directory.join(format!("{}.png", name));
+        let actual_path = self.directory.join(format!("{}.actual.png", name));
+        let diff_path = self.directory.join(format!("{}.diff.png", name));
+
+        if self.update || !golden_path.exists() {
+            std::fs::create_dir_all(&self.directory)?;
+
+            return snapshot.save_png(&golden_path);
+        }
+
+        let golden = Snapshot::load_png(&golden_path)?;
+        let result = snapshot.compare(&golden, self.tolerance);
+
+        if result.is_match() {
+            return Ok(());
+        }
+
+        // Empty snapshots cannot be saved as images, but still fail the comparison.
+        if snapshot.size.w > 0 && snapshot.size.h > 0 {
+            snapshot.save_png(&actual_path)?;
+        }
+
+        if let Some(diff) = result.get_diff() {
+            diff.save_png(&diff_path)?;
+        }
+
+        Err(PushrodError::SnapshotMismatch(format!(
+            "[GoldenTester::assert_matches] Snapshot '{}' does not match {}: {} pixels mismatched \
+             (size={}x{}, golden size={}x{}, tolerance={})",
+            name,
+            golden_path.display(),
+            result.get_mismatched(),
+            snapshot.size.w,
+            snapshot.size.h,
+            golden.size.w,
+            golden.size.h,
+            self.tolerance
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a `Snapshot` filled with a single color.
+    fn solid(size: Size, color: [u8; 4]) -> Snapshot {
+        let pixels = color.repeat(size.w as usize * size.h as usize);
+
+        Snapshot::new(size, pixels).unwrap()
+    }
+
+    /// Returns a directory under the system temporary directory that is unique to the test.
+    fn temp_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("pushrod-snapshot-{}-{}", std::process::id(), name));
+
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    #[test]
+    fn test_compare_identical() {
+        let snapshot = solid(Size::new(4, 3), [10, 20, 30, 255]);
+        let result = snapshot.compare(&snapshot.clone(), 0);
+
+        assert!(result.is_match());
+        assert!(!result.is_size_mismatch());
+        assert_eq!(result.get_mismatched(), 0);
+        assert_eq!(result.get_diff().unwrap().get_size(), Size::new(4, 3));
+    }
+
+    #[test]
+    fn test_compare_tolerance() {
+        let expected = solid(Size::new(2, 2), [100, 100, 100, 255]);
+        let mut pixels = expected.get_pixels().to_vec();
+
+        pixels[0] = 103;
+
+        let actual = Snapshot::new(Size::new(2, 2), pixels).unwrap();
+
+        assert_eq!(actual.compare(&expected, 2).get_mismatched(), 1);
+        assert!(actual.compare(&expected, 3).is_match());
+    }
+
+    #[test]
+    fn test_compare_diff_marks_mismatches() {
+        let expected = solid(Size::new(2, 1), [0, 0, 0, 255]);
+        let actual = Snapshot::new(Size::new(2, 1), vec![0, 0, 0, 255, 255, 255, 255, 255]).unwrap();
+        let result = actual.compare(&expected, 0);
+        let diff = result.get_diff().unwrap();
+
+        assert_eq!(result.get_mismatched(), 1);
+        assert_eq!(diff.get_pixel(1, 0), Color::RGBA(255, 0, 0, 255));
+        assert_ne!(diff.get_pixel(0, 0), Color::RGBA(255, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_compare_size_mismatch() {
+        let expected = solid(Size::new(4, 4), [0, 0, 0, 255]);
+        let actual = solid(Size::new(4, 2), [0, 0, 0, 255]);
+        let result = actual.compare(&expected, 255);
+
+        assert!(!result.is_match());
+        assert!(result.is_size_mismatch());
+        assert_eq!(result.get_mismatched(), 16);
+        assert!(result.get_diff().is_none());
+    }
+
+    #[test]
+    fn test_compare_empty_never_matches() {
+        let expected = solid(Size::new(4, 4), [0, 0, 0, 255]);
+
+        assert!(!solid(Size::new(0, 0), [0, 0, 0, 255]).compare(&expected, 0).is_match());
+        assert!(!solid(Size::new(0, 4), [0, 0, 0, 255]).compare(&expected, 0).is_match());
+        assert!(!expected.compare(&solid(Size::new(0, 0), [0, 0, 0, 255]), 0).is_match());
+    }
+
+    #[test]
+    fn test_new_size_mismatch() {
+        assert!(Snapshot::new(Size::new(2, 2), vec![0; 2 * 2 * 4]).is_ok());
+        assert!(matches!(
+            Snapshot::new(Size::new(2, 2), vec![0; 3 * 4]),
+            Err(PushrodError::SnapshotSize { len: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let directory = temp_directory("round-trip");
+        let path = directory.join("image.png");
+        let pixels = (0..3 * 2 * 4).map(|x| (x * 10) as u8).collect::<Vec<u8>>();
+        let snapshot = Snapshot::new(Size::new(3, 2), pixels).unwrap();
+
+        snapshot.save_png(&path).unwrap();
+
+        let loaded = Snapshot::load_png(&path).unwrap();
+
+        assert_eq!(loaded.get_size(), snapshot.get_size());
+        assert_eq!(loaded.get_pixels(), snapshot.get_pixels());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_load_png_missing_file() {
+        assert!(Snapshot::load_png(temp_directory("missing").join("missing.png")).is_err());
+    }
+
+    #[test]
+    fn test_golden_tester() {
+        let directory = temp_directory("golden");
+        let mut tester = GoldenTester::new(&directory);
+        let golden = solid(Size::new(2, 2), [0, 0, 255, 255]);
+
+        tester.set_update(false);
+
+        // The golden image is created the first time it is compared.
+        tester.assert_matches("box", &golden).unwrap();
+        assert!(directory.join("box.png").exists());
+        tester.assert_matches("box", &golden).unwrap();
+
+        let result = tester.assert_matches("box", &solid(Size::new(2, 2), [255, 0, 0, 255]));
+
+        assert!(matches!(result, Err(PushrodError::SnapshotMismatch(_))));
+        assert!(directory.join("box.actual.png").exists());
+        assert!(directory.join("box.diff.png").exists());
+
+        let result = tester.assert_matches("box", &solid(Size::new(0, 0), [0, 0, 0, 255]));
+
+        assert!(matches!(result, Err(PushrodError::SnapshotMismatch(_))));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -25,12 +25,13 @@ use sdl2::video::Window;
 use std::any::Any;
 use sdl2::rect::Rect;
 use crate::base_widget::BaseWidget;
-use crate::event::PushrodEvent;
+use crate::error::PushrodError;
+use crate::event::{EventContext, PushrodEvent};
 use crate::font::FontCache;
 use crate::geometry::{origin_point, Point, rect, Size};
 use crate::texture::TextureStore;
 use crate::impl_widget_base;
-use crate::widget::Widget;
+use crate::widget::{Widget, WidgetId};
 
 pub enum TextJustify {
     Left,
@@ -39,10 +40,12 @@ pub enum TextJustify {
 }
 
 pub struct TextWidget {
-    id: i32,
+    id: WidgetId,
     origin: Point,
     size: Size,
     invalidated: bool,
+    visible: bool,
+    enabled: bool,
     texture: TextureStore,
     base_widget: BaseWidget,
     font_name: String,
@@ -54,47 +57,74 @@ pub struct TextWidget {
 }
 
 impl Widget for TextWidget {
-    fn handle_event(&self, _event: PushrodEvent) -> Option<&[PushrodEvent]> { None }
+    fn handle_event(&mut self, _event: PushrodEvent, _context: &mut EventContext) {}
 
-    fn draw(&mut self, c: &mut Canvas<Window>, fc: &mut FontCache) -> Option<&Texture> {
+    fn draw(&mut self, c: &mut Canvas<Window>, fc: &mut FontCache) -> Result<Option<&Texture>, PushrodError> {
         if self.invalidated {
-            self.texture.create_or_resize_texture(c, self.size);
+            self.texture.create_or_resize_texture(c, self.size)?;
 
-            let base_widget_texture = self.base_widget.draw(c, fc).unwrap();
+            let base_widget_texture = self.base_widget.draw(c, fc)?;
             let widget_width = self.size.w;
-            let ( font_texture, font_width, font_height ) = &fc.render_text(
-                c, self.font_name.clone(), self.font_size, self.font_style, self.font_color,
-                self.msg.clone(), widget_width
-            );
-            let texture_y = 0;
-            let texture_x = match self.justification {
-                TextJustify::Left => 0,
-                TextJustify::Right => self.size.w as i32 - *font_width as i32,
-                TextJustify::Center => (self.size.w as i32 - *font_width as i32) / 2,
+
+            // `SDL2_ttf` cannot render empty text, so only the base is painted, clearing any
+            // previous text.
+            let font_texture = if self.msg.is_empty() {
+                None
+            } else {
+                Some(fc.render_text(
+                    c, self.font_name.clone(), self.font_size, self.font_style, self.font_color,
+                    self.msg.clone(), widget_width
+                )?)
             };
 
-            #[allow(unused_must_use)]
-            c.with_texture_canvas(self.texture.get_mut_ref(), |texture| {
-                &texture
-                    .copy(
-                        &base_widget_texture,
-                        None,
-                        rect(0, 0, self.size.w, self.size.h)
-                    )
-                    .unwrap();
+            let mut result = Ok(());
 
-                &texture
-                    .copy(
-                        &font_texture,
+            let copied = c.with_texture_canvas(self.texture.get_mut_ref(), |texture| {
+                if let Some(base_widget_texture) = base_widget_texture {
+                    result = texture.copy(
+                        base_widget_texture,
                         None,
-                        Rect::new(texture_x as i32, texture_y, *font_width, *font_height),
-                    )
-                    .unwrap();
-            })
-                .unwrap();
+                        rect(0, 0, self.size.w, self.size.h)
+                    );
+                }
+
+                if let Some((font_texture, font_width, font_height)) = &font_texture {
+                    let texture_y = 0;
+                    let texture_x = match self.justification {
+                        TextJustify::Left => 0,
+                        TextJustify::Right => self.size.w as i32 - *font_width as i32,
+                        TextJustify::Center => (self.size.w as i32 - *font_width as i32) / 2,
+                    };
+
+                    if result.is_ok() {
+                        result = texture.copy(
+                            font_texture,
+                            None,
+                            Rect::new(texture_x, texture_y, *font_width, *font_height),
+                        );
+                    }
+                }
+            });
+
+            // The rendered text is only needed for this draw.  Textures are not destroyed when
+            // dropped, so it is destroyed explicitly, whether or not it could be copied.
+            if let Some((font_texture, _, _)) = font_texture {
+                unsafe {
+                    font_texture.destroy();
+                }
+            }
+
+            copied?;
+            result.map_err(PushrodError::Render)?;
         }
 
-        self.texture.get_optional_ref()
+        Ok(self.texture.get_optional_ref())
+    }
+
+    /// Destroys the `Texture`s of the `TextWidget`, its base.
+    fn destroy_textures(&mut self) {
+        self.texture.destroy();
+        self.base_widget.destroy_textures();
     }
 
     impl_widget_base!();
@@ -112,10 +142,12 @@ impl TextWidget {
         msg: String,
     ) -> Self {
         Self {
-            id: 0,
+            id: WidgetId::default(),
             origin,
             size,
             invalidated: true,
+            visible: true,
+            enabled: true,
             texture: TextureStore::default(),
             base_widget: BaseWidget::new(origin_point(), size),
             font_name,
@@ -20,7 +20,9 @@
 //! The `Canvas` and `Texture` refer to the `sdl2::render` crate.  Please visit that crate for
 //! more information on the drawing features available.
 
+use crate::error::PushrodError;
 use crate::geometry::Size;
+use log::debug;
 use sdl2::render::{Canvas, Texture};
 use sdl2::video::Window;
 
@@ -61,15 +63,36 @@ impl TextureStore {
 
     /// Creates or resizes the texture for drawing.  It will create a new `Texture` object
     /// to draw against if the `store` object currently does not contain a `Texture`, or if
-    /// the object size has changed.
-    pub fn create_or_resize_texture(&mut self, c: &mut Canvas<Window>, size: Size) {
+    /// the object size has changed.  The previous `Texture`, if any, is destroyed once its
+    /// replacement has been created.  Returns an error if the `Texture` could not be created,
+    /// such as when `size` has a zero width or height.
+    pub fn create_or_resize_texture(&mut self, c: &mut Canvas<Window>, size: Size) -> Result<(), PushrodError> {
         if self.store.is_none() || self.size.w != size.w || self.size.h != size.h {
+            if size.w == 0 || size.h == 0 {
+                return Err(PushrodError::TextureCreate {
+                    size,
+                    message: String::from("Textures cannot be zero-sized."),
+                });
+            }
+
+            let texture = c
+                .create_texture_target(None, size.w, size.h)
+                .map_err(|e| PushrodError::texture(size, e))?;
+
+            // Textures are not destroyed when dropped, so the previous `Texture` is destroyed
+            // explicitly.
+            if let Some(previous) = self.store.replace(texture) {
+                unsafe {
+                    previous.destroy();
+                }
+            }
+
             self.size.w = size.w;
             self.size.h = size.h;
-            self.store = Some(c.create_texture_target(None, size.w, size.h).unwrap());
 
-            eprintln!(
-                "[create_or_resize_texture] Created texture: size={}x{} (memory={})",
+            debug!(
+                target: "pushrod::texture",
+                "Created texture: size={}x{} (memory={})",
                 size.w,
                 size.h,
                 size.get_memory_size()
@@ -77,6 +100,24 @@ impl TextureStore {
 
             self.set_invalidated(true);
         }
+
+        Ok(())
+    }
+
+    /// Destroys the stored `Texture`, releasing its GPU memory.  As the `unsafe_textures` feature
+    /// is enabled, a `Texture` is not destroyed when it is dropped, so this must be called once
+    /// the `TextureStore` is no longer drawn.  The `Canvas` that created the `Texture` must still
+    /// exist.  The `TextureStore` is invalidated, so that a new `Texture` is created if it is drawn
+    /// again.
+    pub fn destroy(&mut self) {
+        if let Some(texture) = self.store.take() {
+            unsafe {
+                texture.destroy();
+            }
+        }
+
+        self.size = Size::default();
+        self.set_invalidated(true);
     }
 
     /// Returns `true` if the object needs to be redrawn to the screen, `false` otherwise.
@@ -0,0 +1,199 @@
+// Timers
+// Pushrod
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Timers
+//!
+//! One-shot and repeating timers, scheduled by the `Engine` run loop.
+//!
+//! Timers are added to the `Engine` using `add_timer`, or from an `EventHandler` using the
+//! `HandlerContext`.  When a timer fires, a `Timer` event containing its `TimerId` is sent to its
+//! target `Widget`, or to the `EventHandler` if it has no target.  One-shot timers fire once,
+//! while repeating timers fire every interval until they are cancelled.
+//!
+//! Repeating timers are scheduled relative to the time at which they were due, rather than the
+//! time at which they fired, so that they do not drift with the frame rate.  If a repeating timer
+//! falls more than an interval behind, such as when the run loop is blocked, it fires once and
+//! is rescheduled from the current time, rather than firing repeatedly to catch up.  Timers whose
+//! target `Widget` has been removed are cancelled automatically.
+
+use crate::widget::WidgetId;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Identifies a timer registered with an `Engine`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+impl fmt::Display for TimerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A single scheduled timer.
+struct Timer {
+    id: TimerId,
+    deadline: Instant,
+    interval: Duration,
+    repeat: bool,
+    target: Option<WidgetId>,
+}
+
+/// The list of timers scheduled by an `Engine`.
+#[derive(Default)]
+pub(crate) struct TimerQueue {
+    timers: Vec<Timer>,
+    next_id: u64,
+}
+
+impl TimerQueue {
+    /// Schedules a timer that fires `interval` after `now`, and every `interval` after that if
+    /// `repeat` is set.  Returns the ID of the new timer.
+    pub(crate) fn add(&mut self, now: Instant, interval: Duration, repeat: bool, target: Option<WidgetId>) -> TimerId {
+        let id = TimerId(self.next_id);
+
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id,
+            deadline: now + interval,
+            interval,
+            repeat,
+            target,
+        });
+
+        id
+    }
+
+    /// Cancels a timer.  Returns `true` if the timer was scheduled, `false` if it had already
+    /// fired, or was already cancelled.
+    pub(crate) fn cancel(&mut self, id: TimerId) -> bool {
+        let count = self.timers.len();
+
+        self.timers.retain(|x| x.id != id);
+        self.timers.len() != count
+    }
+
+    /// Retrieves the time at which the next timer is due, `None` if no timers are scheduled.
+    pub(crate) fn get_next_deadline(&self) -> Option<Instant> {
+        self.timers.iter().map(|x| x.deadline).min()
+    }
+
+    /// Returns the ID and target of each timer that is due at `now`, in the order in which they
+    /// were due.  One-shot timers are removed, and repeating timers are rescheduled.
+    pub(crate) fn poll_due(&mut self, now: Instant) -> Vec<(TimerId, Option<WidgetId>)> {
+        let mut due: Vec<(Instant, TimerId, Option<WidgetId>)> = self
+            .timers
+            .iter()
+            .filter(|x| x.deadline <= now)
+            .map(|x| (x.deadline, x.id, x.target))
+            .collect();
+
+        due.sort_by_key(|x| x.0);
+
+        self.timers.retain(|x| x.repeat || x.deadline > now);
+
+        for timer in self.timers.iter_mut().filter(|x| x.deadline <= now) {
+            timer.deadline += timer.interval;
+
+            if timer.deadline <= now {
+                timer.deadline = now + timer.interval;
+            }
+        }
+
+        due.into_iter().map(|(_, id, target)| (id, target)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the `Instant` that is `ms` milliseconds after `start`.
+    fn at(start: Instant, ms: u64) -> Instant {
+        start + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_one_shot_fires_once() {
+        let start = Instant::now();
+        let mut timers = TimerQueue::default();
+        let target = Some(WidgetId::new(3, 1));
+        let id = timers.add(start, Duration::from_millis(100), false, target);
+
+        assert_eq!(timers.get_next_deadline(), Some(at(start, 100)));
+        assert!(timers.poll_due(at(start, 99)).is_empty());
+        assert_eq!(timers.poll_due(at(start, 100)), vec![(id, target)]);
+        assert!(timers.poll_due(at(start, 500)).is_empty());
+        assert_eq!(timers.get_next_deadline(), None);
+    }
+
+    #[test]
+    fn test_repeating_does_not_drift() {
+        let start = Instant::now();
+        let mut timers = TimerQueue::default();
+        let id = timers.add(start, Duration::from_millis(100), true, None);
+
+        // Firing late does not delay the next deadline.
+        assert_eq!(timers.poll_due(at(start, 130)), vec![(id, None)]);
+        assert_eq!(timers.get_next_deadline(), Some(at(start, 200)));
+
+        assert!(timers.poll_due(at(start, 199)).is_empty());
+        assert_eq!(timers.poll_due(at(start, 210)), vec![(id, None)]);
+        assert_eq!(timers.get_next_deadline(), Some(at(start, 300)));
+    }
+
+    #[test]
+    fn test_repeating_fires_once_when_behind() {
+        let start = Instant::now();
+        let mut timers = TimerQueue::default();
+        let id = timers.add(start, Duration::from_millis(100), true, None);
+
+        // More than an interval behind: fires once, and is rescheduled from the current time.
+        assert_eq!(timers.poll_due(at(start, 350)), vec![(id, None)]);
+        assert_eq!(timers.get_next_deadline(), Some(at(start, 450)));
+        assert!(timers.poll_due(at(start, 449)).is_empty());
+        assert_eq!(timers.poll_due(at(start, 450)), vec![(id, None)]);
+    }
+
+    #[test]
+    fn test_due_timers_are_ordered_by_deadline() {
+        let start = Instant::now();
+        let mut timers = TimerQueue::default();
+        let late = timers.add(start, Duration::from_millis(50), false, None);
+        let early = timers.add(start, Duration::from_millis(20), true, None);
+        let pending = timers.add(start, Duration::from_millis(80), false, None);
+
+        assert_eq!(timers.poll_due(at(start, 60)), vec![(early, None), (late, None)]);
+        assert_eq!(timers.get_next_deadline(), Some(at(start, 80)));
+        assert_eq!(timers.poll_due(at(start, 80)), vec![(early, None), (pending, None)]);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let start = Instant::now();
+        let mut timers = TimerQueue::default();
+        let repeating = timers.add(start, Duration::from_millis(100), true, None);
+        let one_shot = timers.add(start, Duration::from_millis(100), false, None);
+
+        assert_ne!(repeating, one_shot);
+        assert!(timers.cancel(repeating));
+        assert!(!timers.cancel(repeating));
+        assert_eq!(timers.poll_due(at(start, 100)), vec![(one_shot, None)]);
+
+        // One-shot timers that have already fired cannot be cancelled.
+        assert!(!timers.cancel(one_shot));
+        assert_eq!(timers.get_next_deadline(), None);
+    }
+}
@@ -20,34 +20,79 @@
 
 use crate::base_widget::BaseWidget;
 use crate::box_widget::BoxWidget;
-use crate::event::PushrodEvent;
+use crate::error::PushrodError;
+use crate::event::{EventContext, PushrodEvent};
 use crate::geometry::{Point, Size};
 use crate::texture::TextureStore;
 use sdl2::pixels::Color;
 use sdl2::render::{Canvas, Texture};
 use sdl2::video::Window;
 use std::any::Any;
+use std::fmt;
 use crate::button_widget::ButtonWidget;
 use crate::font::FontCache;
 use crate::text_widget::TextWidget;
 
+/// A handle to a `Widget` stored in a `WidgetCache`, assigned when the `Widget` is added.
+///
+/// The handle consists of the index of the slot in which the `Widget` is stored, and the
+/// generation of that slot.  When a `Widget` is removed, its slot may be reused by a new `Widget`,
+/// but the generation of the slot changes, so a handle to a removed `Widget` can never refer to
+/// the `Widget` that replaced it.
+///
+/// The default `WidgetId` refers to the top-level `Widget` of a `WidgetCache`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WidgetId {
+    index: u32,
+    generation: u32,
+}
+
+impl WidgetId {
+    /// Creates a new `WidgetId` from a slot index and generation.
+    pub(crate) fn new(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    /// Returns the index of the slot in the `WidgetCache` in which the `Widget` is stored.
+    pub fn get_index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the generation of the slot in which the `Widget` is stored.
+    pub fn get_generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl fmt::Display for WidgetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.index, self.generation)
+    }
+}
+
 /// `Widget` is a drawable, interactive object that is presented in a `Window`.
 ///
 /// `Widget`s are components that are used to display information to a user, as well as to provide
 /// user-interactable objects.  These make up things like text messages, images, 3D graphics, and
 /// so on.
 pub trait Widget {
-    /// Returns the ID of the `Widget`, assigned by the `Engine`.
-    fn get_id(&self) -> i32;
+    /// Returns the ID of the `Widget`, assigned by the `WidgetCache` when the `Widget` is added.
+    fn get_id(&self) -> WidgetId;
 
-    /// Sets the ID of the `Widget`
-    fn set_id(&mut self, id: i32);
+    /// Sets the ID of the `Widget`.  This is called by the `WidgetCache`, and should not need to
+    /// be called directly.
+    fn set_id(&mut self, id: WidgetId);
 
     /// Returns the pure form of the object for casting, if required.  This is used internally by
     /// the `Pushrod` event system, and should always just return `self`.  (See #impl_widget_base
     /// macro)
     fn as_any(&self) -> &dyn Any;
 
+    /// Returns the pure form of the object for mutable casting.  This is used to downcast a
+    /// `Widget` to its concrete type, and should always just return `self`.  (See
+    /// #impl_widget_base macro)
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
     /// Gets the origin (upper left-hand) coordinate of the `Widget`.  This is the physical location
     /// of the `Widget` on the screen.
     fn get_origin(&self) -> &Point;
@@ -58,10 +103,13 @@ pub trait Widget {
     /// Gets the color of the `Widget`.
     fn get_color(&self) -> Color;
 
-    /// Sets the origin point.
+    /// Sets the origin point.  Use `WidgetCache::set_origin` to make sure the areas the `Widget`
+    /// previously occupied, and now occupies, are redrawn.
     fn set_origin(&mut self, point: Point);
 
-    /// Sets the size of the `Widget`.  Sub-`Widget` objects must also be resized.
+    /// Sets the size of the `Widget`.  Sub-`Widget` objects must also be resized.  Use
+    /// `WidgetCache::set_size` to make sure the areas the `Widget` previously occupied, and now
+    /// occupies, are redrawn.
     fn set_size(&mut self, size: Size);
 
     /// Sets the invalidation state of the object.  When invalidated, it indicates to the
@@ -81,25 +129,62 @@ pub trait Widget {
     /// saving that contents to a texture, and displaying it to the screen during the draw cycle.
     fn is_invalidated(&self) -> bool;
 
+    /// Sets whether or not the `Widget` is visible.  Hidden `Widget`s, and their children, are not
+    /// drawn, and are ignored when locating the `Widget` under the mouse pointer.  Use
+    /// `WidgetCache::set_visible` to make sure the area the `Widget` occupies is redrawn.
+    fn set_visible(&mut self, visible: bool);
+
+    /// Indicates whether or not the `Widget` is visible.
+    fn is_visible(&self) -> bool;
+
+    /// Sets whether or not the `Widget` is enabled.  Disabled `Widget`s, and their children, are
+    /// drawn with a dimmed overlay, and do not receive interaction events, so they never generate
+    /// events such as `Clicked`.
+    fn set_enabled(&mut self, enabled: bool);
+
+    /// Indicates whether or not the `Widget` is enabled.
+    fn is_enabled(&self) -> bool;
+
+    /// Cancels any interaction in progress, such as a mouse button held down over the `Widget`.
+    /// This is called by the `WidgetCache` when the `Widget`, or one of its parents, is disabled,
+    /// as the `Widget` no longer receives the events that would end the interaction.  Defaults to
+    /// doing nothing.
+    fn cancel_interaction(&mut self) {}
+
+    /// Indicates whether or not the `Widget` can receive keyboard focus, either by being clicked, or
+    /// by using `Tab` and `Shift-Tab` to move between `Widget`s.  The focused `Widget` receives
+    /// all keyboard and text input events.  Defaults to `false`.
+    fn is_focusable(&self) -> bool {
+        false
+    }
+
     /// Retrieves the `TextureStore` for the `Widget`.  The `TextureStore` is the object's
     /// drawing GPU texture that gets blitted to the screen.  Only update the texture if
     /// invalidated.
     fn get_texture(&mut self) -> &mut TextureStore;
 
-    /// Function that responds to a wrapped event from `SDL2`, and generates an optional
-    /// `PushrodEvent` as a result of the event processing.
+    /// Destroys the `Texture`s of the `Widget`, releasing their GPU memory.  This is called by the
+    /// `WidgetCache` when the `Widget` is removed.  `Widget`s that draw using sub-`Widget`s must
+    /// also destroy the `Texture`s of their sub-`Widget`s.  Defaults to destroying the
+    /// `TextureStore` returned by `get_texture`.
+    fn destroy_textures(&mut self) {
+        self.get_texture().destroy();
+    }
+
+    /// Function that responds to an event sent to the `Widget`, such as a wrapped event from
+    /// `SDL2`, or an event generated by `Pushrod`.
     ///
-    /// If an event is generated by the `Widget` after handling an event, it should be returned as
-    /// an optional return.  Otherwise, if the `Widget` consumes the event without generating a
-    /// responding event, it should return `None`.  **Most** `Widget`s will return `None`, however,
-    /// something like a timer or other widget could create a custom event.  If so, it should be
-    /// returned here so that other `Widget` objects, or the underlying application, can handle
-    /// the event appropriately.
-    fn handle_event(&self, event: PushrodEvent) -> Option<&[PushrodEvent]>;
+    /// The `Widget` may update its own state in response.  Any `PushrodEvent`s generated by the
+    /// `Widget` should be pushed to the `EventContext`, so that other `Widget` objects, or the
+    /// underlying application, can handle the event appropriately.  **Most** `Widget`s will not
+    /// generate any events, however, something like a button or timer could.  The `EventContext`
+    /// can also be used to request redraws, and to query the `WidgetCache`.
+    fn handle_event(&mut self, event: PushrodEvent, context: &mut EventContext);
 
     /// Copies the image stored inside the `Widget` to the screen.  If the `Widget` doesn't
     /// actually _draw_ anything, it can return `None`.  Otherwise, it returns a reference
-    /// to the stored `Texture`.
+    /// to the stored `Texture`.  Returns an error if the `Widget` could not be drawn, such as
+    /// when its font could not be loaded, or its `Texture` could not be created.
     ///
     /// Drawing is conducted from an origin of `x=0` and `y=0` as the base coordinates.  The only other
     /// bounds that should be referenced are the `size` coordinates, meaning the `width` and
@@ -108,7 +193,7 @@ pub trait Widget {
     /// All drawing is always done from an origin point, as the actual `Widget`'s `get_origin`
     /// point is indicated to the `Pushrod` system as to where the `Widget` should be drawn in
     /// the user viewable space (canvas).
-    fn draw(&mut self, c: &mut Canvas<Window>, fc: &mut FontCache) -> Option<&Texture>;
+    fn draw(&mut self, c: &mut Canvas<Window>, fc: &mut FontCache) -> Result<Option<&Texture>, PushrodError>;
 }
 
 /// System Widgets.
@@ -127,10 +212,35 @@ pub enum SystemWidget {
     /// around the frame of the button, and a 3D-like appearance within its specified border width.
     Button(Box<ButtonWidget>),
 
-    /// Stores a custom `Widget`.
+    /// Stores a custom `Widget`.  Custom `Widget`s receive events, are hit-tested, and are drawn
+    /// in exactly the same way as the system `Widget`s.
     Custom(Box<dyn Widget>),
 }
 
+impl SystemWidget {
+    /// Returns a reference to the `Widget` stored in this `SystemWidget`.
+    pub fn as_widget(&self) -> &dyn Widget {
+        match self {
+            SystemWidget::Base(x) => x.as_ref(),
+            SystemWidget::Box(x) => x.as_ref(),
+            SystemWidget::Text(x) => x.as_ref(),
+            SystemWidget::Button(x) => x.as_ref(),
+            SystemWidget::Custom(x) => x.as_ref(),
+        }
+    }
+
+    /// Returns a mutable reference to the `Widget` stored in this `SystemWidget`.
+    pub fn as_widget_mut(&mut self) -> &mut dyn Widget {
+        match self {
+            SystemWidget::Base(x) => x.as_mut(),
+            SystemWidget::Box(x) => x.as_mut(),
+            SystemWidget::Text(x) => x.as_mut(),
+            SystemWidget::Button(x) => x.as_mut(),
+            SystemWidget::Custom(x) => x.as_mut(),
+        }
+    }
+}
+
 /// impl_widget_base! macro is used as a shortcut for any `impl` that does not implement its own
 /// custom functionality for any of the base functions in `Widget`, aside from `handle_event`
 /// and `draw`.
@@ -140,8 +250,8 @@ pub enum SystemWidget {
 ///
 /// ```rust,no_run
 /// struct MyWidget {
-///   /// ID of the widgetI'
-///   id: u32,
+///   /// ID of the widget
+///   id: WidgetId,
 ///
 ///   /// Point of origin of the current `Widget`
 ///   origin: Point,
@@ -152,6 +262,12 @@ pub enum SystemWidget {
 ///   /// Store for the invalidation draw flag.
 ///   invalidated: bool,
 ///
+///   /// Store for the visibility flag.
+///   visible: bool,
+///
+///   /// Store for the enabled flag.
+///   enabled: bool,
+///
 ///   /// Store for the drawing texture.
 ///   texture: TextureStore,
 ///
@@ -163,14 +279,35 @@ pub enum SystemWidget {
 ///
 /// Note, any `Widget` that is drawn must draw against the `base_widget`, which utilizes the
 /// object inheritance using composition.
+///
+/// `Widget`s that need to respond to being enabled or disabled can use
+/// `impl_widget_base!(custom_set_enabled)`, which leaves out `set_enabled`, so that it can be
+/// implemented by the `Widget`.
 #[macro_export]
 macro_rules! impl_widget_base {
     () => {
+        $crate::impl_widget_base!(@common);
+
+        /// Default implementation, sets the enabled flag, invalidating the `Widget`.
+        fn set_enabled(&mut self, enabled: bool) {
+            self.enabled = enabled;
+            self.set_invalidated(true);
+        }
+    };
+
+    (custom_set_enabled) => {
+        $crate::impl_widget_base!(@common);
+    };
+
+    (@common) => {
         /// Default implementation, returning the object structure as an `Any`.
         fn as_any(&self) -> &dyn Any { self }
 
+        /// Default implementation, returning the object structure as a mutable `Any`.
+        fn as_any_mut(&mut self) -> &mut dyn Any { self }
+
         /// Default implementation, returns the ID of this `Widget`.
-        fn get_id(&self) -> i32 { self.id }
+        fn get_id(&self) -> WidgetId { self.id }
 
         /// Default implementation, returns the `Point` of origin of this `Widget`.
         fn get_origin(&self) -> &Point { &self.origin }
@@ -182,7 +319,7 @@ macro_rules! impl_widget_base {
         fn get_color(&self) -> Color { self.base_widget.get_color() }
 
         /// Default implementation, sets the `ID` of this `Widget`.
-        fn set_id(&mut self, id: i32) { self.id = id; }
+        fn set_id(&mut self, id: WidgetId) { self.id = id; }
 
         /// Default implementation, sets the `Point` of origin of this object.
         fn set_origin(&mut self, point: Point) { self.origin = point; }
@@ -209,6 +346,18 @@ macro_rules! impl_widget_base {
         /// Default implementation, returns the invalidation flag.
         fn is_invalidated(&self) -> bool { self.invalidated }
 
+        /// Default implementation, sets the visibility flag, invalidating the `Widget`.
+        fn set_visible(&mut self, visible: bool) {
+            self.visible = visible;
+            self.set_invalidated(true);
+        }
+
+        /// Default implementation, returns the visibility flag.
+        fn is_visible(&self) -> bool { self.visible }
+
+        /// Default implementation, returns the enabled flag.
+        fn is_enabled(&self) -> bool { self.enabled }
+
         /// Default implementation, returns the mutable `Widget` `Texture`.
         fn get_texture(&mut self) -> &mut TextureStore { &mut self.texture }
     };